@@ -0,0 +1,96 @@
+use super::history::{unix_now, SECONDS_PER_DAY};
+use super::GitAnalyzer;
+use crate::data::CodeAge;
+use anyhow::{Context, Result};
+use git2::{BlameOptions, Oid, Repository};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 1ファイル分のblameの集計結果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BlameSummary {
+    /// blameした行数
+    pub lines: usize,
+    /// 作者ごとの現存行数（多い順）
+    pub owners: Vec<LineOwner>,
+    /// 現存行の経過日数の分布（`CodeAge::days_category` のカテゴリごとの行数）
+    pub age_distribution: [usize; 5],
+}
+
+/// ある作者が書いた現存行数
+#[derive(Debug, Clone, Serialize)]
+pub struct LineOwner {
+    pub author: String,
+    pub lines: usize,
+}
+
+/// コミット時点のファイルをblameして集計する（作者名はmailmapで名寄せする）
+pub(super) fn summarize(repo: &Repository, commit: Oid, relative_path: &Path) -> Result<BlameSummary> {
+    let mut options = BlameOptions::new();
+    options.newest_commit(commit).use_mailmap(true);
+    let blame = repo.blame_file(relative_path, Some(&mut options))?;
+
+    let now = unix_now();
+    let mut summary = BlameSummary::default();
+    let mut owners: HashMap<String, usize> = HashMap::new();
+
+    for hunk in blame.iter() {
+        let lines = hunk.lines_in_hunk();
+        let signature = hunk.final_signature();
+        let author = signature
+            .name()
+            .or_else(|| signature.email())
+            .unwrap_or("unknown")
+            .to_string();
+        let days = (now - signature.when().seconds()).max(0) / SECONDS_PER_DAY;
+
+        summary.lines += lines;
+        *owners.entry(author).or_insert(0) += lines;
+        summary.age_distribution[CodeAge::days_category(days)] += lines;
+    }
+
+    summary.owners = owners
+        .into_iter()
+        .map(|(author, lines)| LineOwner { author, lines })
+        .collect();
+    summary
+        .owners
+        .sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.author.cmp(&b.author)));
+
+    Ok(summary)
+}
+
+/// リビジョン（省略時はHEAD）に含まれる全ファイルをblameし、JSONとして書き出す
+/// 戻り値は書き出したファイル数
+pub fn export_blame(root: &Path, rev: Option<&str>, output: &Path) -> Result<usize> {
+    let analyzer = GitAnalyzer::new(root)?;
+    let commit = analyzer.resolve_commit(rev.unwrap_or("HEAD"))?;
+    // 空のファイルとバイナリファイル（0行）はblameの対象外
+    let paths: Vec<PathBuf> = analyzer
+        .line_counts_at(commit)?
+        .into_iter()
+        .filter(|(_, lines)| *lines > 0)
+        .map(|(path, _)| path)
+        .collect();
+
+    // Repositoryはスレッド間で共有できないため、ワーカーごとに開き直す
+    let summaries = paths
+        .par_iter()
+        .map_init(
+            || GitAnalyzer::new(root),
+            |analyzer, path| {
+                let analyzer = analyzer.as_ref().map_err(|e| anyhow::anyhow!("{}", e))?;
+                let summary = analyzer
+                    .blame_at(commit, path)
+                    .with_context(|| format!("blame failed: {}", path.display()))?;
+                Ok((path.to_string_lossy().to_string(), summary))
+            },
+        )
+        .collect::<Result<BTreeMap<_, _>>>()?;
+
+    fs::write(output, serde_json::to_string_pretty(&summaries)?)?;
+    Ok(summaries.len())
+}
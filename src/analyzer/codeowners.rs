@@ -0,0 +1,403 @@
+use super::history::HistoryMetrics;
+use crate::config::OwnersConfig;
+use crate::data::{FileNode, OwnershipIssues};
+use anyhow::{Context, Result};
+use git2::{Mailmap, Repository, Signature};
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// CODEOWNERSを探す場所（GitHubと同じく最初に見つかったものだけを使う）
+const CODEOWNERS_LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// CODEOWNERSの1行（パターンと所有者）
+#[derive(Debug, Clone)]
+struct OwnerRule {
+    pattern: Regex,
+    /// 空の場合は所有者なしと明示したもの
+    owners: Vec<String>,
+}
+
+/// CODEOWNERSの内容と、所有者ごとのメールアドレス（mailmapで名寄せして小文字にしたもの）
+/// メールアドレスがわからない所有者（設定ファイルで定義していない `@ユーザー名` やチーム）は含まない
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    rules: Vec<OwnerRule>,
+    identities: HashMap<String, BTreeSet<String>>,
+    /// 所有者がいないことを問題にする期間内のコミット数の下限
+    hot_commits: usize,
+}
+
+impl CodeOwners {
+    /// リポジトリのCODEOWNERSを読む（見つからなければNone）
+    pub fn load(root: &Path, config: &OwnersConfig) -> Result<Option<Self>> {
+        let Some(path) = CODEOWNERS_LOCATIONS
+            .iter()
+            .map(|location| root.join(location))
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+
+        let content = fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let mailmap = Repository::discover(root).ok().and_then(|repo| repo.mailmap().ok());
+        Self::parse(&content, &path, config, mailmap.as_ref()).map(Some)
+    }
+
+    /// CODEOWNERSの内容を解析する（`path` はエラーメッセージ用）
+    fn parse(content: &str, path: &Path, config: &OwnersConfig, mailmap: Option<&Mailmap>) -> Result<Self> {
+        let mut rules = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            // 行末のコメントも取り除く（`\#` で始まるパターンは扱わない）
+            let line = line.split_once(" #").map_or(line, |(line, _)| line).trim();
+            // GitLabのセクション見出し（`[Section]`）は読み飛ばす
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let Some(pattern) = fields.next() else {
+                continue;
+            };
+            let pattern = pattern_regex(pattern)
+                .with_context(|| format!("invalid pattern at {}:{}", path.display(), index + 1))?;
+            rules.push(OwnerRule {
+                pattern,
+                owners: fields.map(str::to_string).collect(),
+            });
+        }
+
+        let identities = rules
+            .iter()
+            .flat_map(|rule| &rule.owners)
+            .filter_map(|owner| Some((owner.to_lowercase(), owner_emails(owner, config, mailmap)?)))
+            .collect();
+        Ok(Self {
+            rules,
+            identities,
+            hot_commits: config.hot_commits,
+        })
+    }
+
+    /// ファイルの所有者（リポジトリルートからの相対パス、最後に一致した行が優先される）
+    pub fn owners_of(&self, relative_path: &Path) -> &[String] {
+        let path = relative_path.to_string_lossy().replace('\\', "/");
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.pattern.is_match(&path))
+            .map_or(&[], |rule| &rule.owners)
+    }
+
+    /// CODEOWNERSに現れるすべての所有者（名前順）
+    pub fn all_owners(&self) -> Vec<String> {
+        self.rules
+            .iter()
+            .flat_map(|rule| rule.owners.iter().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// メールアドレス（mailmapで名寄せしたもの）の作者が所有者のいずれかに当たるか
+    /// メールアドレスがわからない所有者が含まれ、他の所有者にも当たらない場合は判定できずNone
+    fn is_owned_by(&self, owners: &[String], email: &str) -> Option<bool> {
+        let email = email.to_lowercase();
+        let mut unknown = false;
+        for owner in owners {
+            match self.identities.get(&owner.to_lowercase()) {
+                Some(emails) if emails.contains(&email) => return Some(true),
+                Some(_) => {}
+                None => unknown = true,
+            }
+        }
+        (!unknown).then_some(false)
+    }
+}
+
+/// 所有者のメールアドレス（わからなければNone）
+/// メールアドレスはそのまま、`@ユーザー名` は設定ファイルの `handles` から、チームはすべてのメンバーから求める
+fn owner_emails(owner: &str, config: &OwnersConfig, mailmap: Option<&Mailmap>) -> Option<BTreeSet<String>> {
+    if owner.starts_with('@') && owner.contains('/') {
+        let members = config_entry(&config.teams, owner)?;
+        let mut emails = BTreeSet::new();
+        for member in members {
+            // チームの中のチームはたどらない
+            if member.contains('/') {
+                return None;
+            }
+            emails.extend(owner_emails(member, config, mailmap)?);
+        }
+        return Some(emails);
+    }
+
+    let emails = match owner.strip_prefix('@') {
+        Some(_) => config_entry(&config.handles, owner)?.to_vec(),
+        None if owner.contains('@') => vec![owner.to_string()],
+        None => return None,
+    };
+    Some(emails.iter().map(|email| canonical_email(email, mailmap)).collect())
+}
+
+/// 設定ファイルの所有者の定義（ユーザー名・チーム名は大文字小文字を区別しない）
+fn config_entry<'a>(entries: &'a HashMap<String, Vec<String>>, owner: &str) -> Option<&'a [String]> {
+    entries
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(owner))
+        .map(|(_, members)| members.as_slice())
+}
+
+/// mailmapで名寄せした小文字のメールアドレス（コミットの作者と同じ名寄せをする）
+fn canonical_email(email: &str, mailmap: Option<&Mailmap>) -> String {
+    let resolved = mailmap.and_then(|mailmap| {
+        let signature = Signature::now(email, email).ok()?;
+        let resolved = mailmap.resolve_signature(&signature).ok()?;
+        resolved.email().map(str::to_string)
+    });
+    resolved.unwrap_or_else(|| email.to_string()).to_lowercase()
+}
+
+/// CODEOWNERSのパターン（gitignoreとほぼ同じ書式）を正規表現に変換する
+/// `/` で始まるか途中に `/` を含むパターンはルートから、それ以外は任意の階層から一致させる
+fn pattern_regex(pattern: &str) -> Result<Regex> {
+    let body = pattern.trim_start_matches('/').trim_end_matches('/');
+    let anchored = pattern.starts_with('/') || body.contains('/');
+    let mut regex = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+
+    let chars: Vec<char> = body.chars().collect();
+    let mut index = 0;
+    while index < chars.len() {
+        match chars[index] {
+            '*' if chars.get(index + 1) == Some(&'*') => {
+                // `**/` は0個以上のディレクトリ、それ以外の `**` は以下すべて
+                if chars.get(index + 2) == Some(&'/') {
+                    regex.push_str("(?:.*/)?");
+                    index += 3;
+                } else {
+                    regex.push_str(".*");
+                    index += 2;
+                }
+                continue;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        index += 1;
+    }
+
+    // `dir/` は配下すべて、`dir/*` は直下のファイルだけ、それ以外は一致したディレクトリの配下も含む
+    regex.push_str(if pattern.ends_with('/') {
+        "/.*$"
+    } else if body.ends_with("/*") {
+        "$"
+    } else {
+        "(?:/.*)?$"
+    });
+    Ok(Regex::new(&regex)?)
+}
+
+/// 所有者と、所有者の問題（期間内に変更されたのに所有者がいない・最も多くコミットした作者が所有者でない）をツリーに反映する
+/// 最も多くコミットした作者は作者で絞り込む前の履歴から求める
+pub fn apply_owners(node: &mut FileNode, root: &Path, codeowners: &CodeOwners, history: &HistoryMetrics) {
+    let mut top_committers: HashMap<&Path, (&str, usize)> = HashMap::new();
+    for (author, metrics) in &history.authors {
+        for (path, &commits) in &metrics.commits {
+            let top = top_committers.entry(path.as_path()).or_insert((author, commits));
+            // 同数の場合は名前順で決める
+            if commits > top.1 || (commits == top.1 && author.as_str() < top.0) {
+                *top = (author, commits);
+            }
+        }
+    }
+
+    apply_node(node, root, codeowners, history, &top_committers);
+}
+
+fn apply_node(
+    node: &mut FileNode,
+    root: &Path,
+    codeowners: &CodeOwners,
+    history: &HistoryMetrics,
+    top_committers: &HashMap<&Path, (&str, usize)>,
+) -> OwnershipIssues {
+    if node.is_dir {
+        node.metrics.ownership_issues = node
+            .children
+            .iter_mut()
+            .map(|child| apply_node(child, root, codeowners, history, top_committers))
+            .fold(OwnershipIssues::default(), |total, child| OwnershipIssues {
+                unowned: total.unowned + child.unowned,
+                mismatched: total.mismatched + child.mismatched,
+            });
+        return node.metrics.ownership_issues;
+    }
+
+    let relative_path = node.path.strip_prefix(root).unwrap_or(&node.path);
+    let owners = codeowners.owners_of(relative_path).to_vec();
+    let top_committer = top_committers.get(relative_path).map(|(author, _)| author.to_string());
+
+    let mut issues = OwnershipIssues::default();
+    if let Some(author) = &top_committer {
+        // 所有者がいないことは期間内に `hot_commits` 回以上変更されたファイルだけ問題にする
+        // （作者で絞り込んだ表示でも変わらないよう、絞り込む前の履歴で数える）
+        if owners.is_empty() {
+            let commits = history.commits.get(relative_path).copied().unwrap_or(0);
+            issues.unowned = usize::from(commits >= codeowners.hot_commits);
+        } else if let Some(email) = history.emails.get(author) {
+            issues.mismatched = usize::from(codeowners.is_owned_by(&owners, email) == Some(false));
+        }
+    }
+
+    node.metrics.owners = owners;
+    node.metrics.top_committer = top_committer;
+    node.metrics.ownership_issues = issues;
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::scanner::build_tree_from_lines;
+    use std::path::PathBuf;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        pattern_regex(pattern).unwrap().is_match(path)
+    }
+
+    #[test]
+    fn anchors_patterns_with_a_slash_to_the_root() {
+        assert!(matches("*.rs", "src/main.rs"));
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("/build", "build/out.txt"));
+        assert!(!matches("/build", "src/build/out.txt"));
+        assert!(matches("docs/api", "docs/api/index.md"));
+        assert!(!matches("docs/api", "src/docs/api/index.md"));
+        assert!(matches("build", "src/build/out.txt"));
+        assert!(!matches("build", "src/builder.rs"));
+    }
+
+    #[test]
+    fn expands_double_stars() {
+        assert!(matches("**/test/*.rs", "test/a.rs"));
+        assert!(matches("**/test/*.rs", "src/deep/test/a.rs"));
+        assert!(matches("src/**/mod.rs", "src/mod.rs"));
+        assert!(matches("src/**/mod.rs", "src/a/b/mod.rs"));
+        assert!(matches("src/**", "src/a/b.rs"));
+        assert!(!matches("src/**/mod.rs", "lib/src/mod.rs"));
+    }
+
+    #[test]
+    fn distinguishes_trailing_slashes_and_single_stars() {
+        assert!(matches("logs/", "logs/today.txt"));
+        assert!(matches("logs/", "app/logs/today.txt"));
+        assert!(!matches("logs/", "logs"));
+        assert!(matches("docs/*", "docs/readme.md"));
+        assert!(!matches("docs/*", "docs/api/readme.md"));
+        assert!(matches("a?c.txt", "abc.txt"));
+        assert!(!matches("a?c.txt", "a/c.txt"));
+        assert!(matches("a+b.txt", "a+b.txt"));
+        assert!(!matches("a+b.txt", "aab.txt"));
+    }
+
+    fn codeowners(content: &str, config: &OwnersConfig, mailmap: Option<&Mailmap>) -> CodeOwners {
+        CodeOwners::parse(content, Path::new("CODEOWNERS"), config, mailmap).unwrap()
+    }
+
+    fn owners(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn uses_the_last_matching_rule() {
+        let codeowners = codeowners(
+            "* @all  # default\n/src/ @core\n/src/generated/\n",
+            &OwnersConfig::default(),
+            None,
+        );
+        assert_eq!(codeowners.owners_of(Path::new("README.md")), owners(&["@all"]));
+        assert_eq!(codeowners.owners_of(Path::new("src/main.rs")), owners(&["@core"]));
+        assert!(codeowners.owners_of(Path::new("src/generated/api.rs")).is_empty());
+    }
+
+    #[test]
+    fn matches_email_owners_through_the_mailmap() {
+        let mailmap = Mailmap::from_buffer("Alice <alice@example.com> <alice@old.example.com>\n").unwrap();
+        let codeowners = codeowners("* alice@old.example.com\n", &OwnersConfig::default(), Some(&mailmap));
+        let owners = owners(&["alice@old.example.com"]);
+        assert_eq!(codeowners.is_owned_by(&owners, "alice@example.com"), Some(true));
+        assert_eq!(codeowners.is_owned_by(&owners, "bob@example.com"), Some(false));
+    }
+
+    #[test]
+    fn judges_handles_and_teams_only_when_configured() {
+        let config = OwnersConfig {
+            handles: HashMap::from([
+                ("@alice".to_string(), vec!["alice@example.com".to_string()]),
+                ("@carol".to_string(), vec!["carol@example.com".to_string()]),
+            ]),
+            teams: HashMap::from([
+                ("@org/core".to_string(), vec!["@Alice".to_string(), "dave@example.com".to_string()]),
+                ("@org/web".to_string(), vec!["@alice".to_string(), "@erin".to_string()]),
+            ]),
+            ..OwnersConfig::default()
+        };
+        let codeowners = codeowners("* @alice @bob @org/core @org/web @org/ops @carol\n", &config, None);
+
+        // 作者名やメールアドレスのローカル部が似ているだけでは所有者とみなさない
+        assert_eq!(codeowners.is_owned_by(&owners(&["@carol"]), "carol@elsewhere.com"), Some(false));
+        assert_eq!(codeowners.is_owned_by(&owners(&["@ALICE"]), "Alice@Example.com"), Some(true));
+        assert_eq!(codeowners.is_owned_by(&owners(&["@org/core"]), "dave@example.com"), Some(true));
+        assert_eq!(codeowners.is_owned_by(&owners(&["@org/core"]), "bob@example.com"), Some(false));
+        // メールアドレスがわからないユーザー・メンバー・チームは判定しない
+        assert_eq!(codeowners.is_owned_by(&owners(&["@bob"]), "bob@example.com"), None);
+        assert_eq!(codeowners.is_owned_by(&owners(&["@org/web"]), "bob@example.com"), None);
+        assert_eq!(codeowners.is_owned_by(&owners(&["@org/ops"]), "bob@example.com"), None);
+        assert_eq!(codeowners.is_owned_by(&owners(&["@bob", "@alice"]), "alice@example.com"), Some(true));
+    }
+
+    #[test]
+    fn flags_unowned_and_mismatched_files() {
+        let root = Path::new("/repo");
+        let lines = ["src/a.rs", "src/b.rs", "docs/c.md", "docs/d.md"]
+            .into_iter()
+            .map(|path| (PathBuf::from(path), 10))
+            .collect();
+        let file_commits = |commits: &[(&str, usize)]| HistoryMetrics {
+            commits: commits.iter().map(|&(path, count)| (PathBuf::from(path), count)).collect(),
+            ..HistoryMetrics::default()
+        };
+        let history = HistoryMetrics {
+            authors: HashMap::from([
+                ("alice".to_string(), file_commits(&[("src/a.rs", 1), ("docs/d.md", 2)])),
+                ("bob".to_string(), file_commits(&[("docs/c.md", 3), ("docs/d.md", 1)])),
+            ]),
+            emails: HashMap::from([
+                ("alice".to_string(), "alice@example.com".to_string()),
+                ("bob".to_string(), "bob@example.com".to_string()),
+            ]),
+            ..file_commits(&[("src/a.rs", 1), ("docs/c.md", 3), ("docs/d.md", 3)])
+        };
+        let issues = |config: &OwnersConfig, path: &str| {
+            let mut tree = build_tree_from_lines(root, &lines);
+            apply_owners(&mut tree, root, &codeowners("/docs/ alice@example.com\n", config, None), &history);
+            let issues = tree.find(&root.join(path)).unwrap().metrics.ownership_issues;
+            (issues.unowned, issues.mismatched)
+        };
+
+        let config = OwnersConfig::default();
+        // 期間内に一度でも変更されていれば、変更頻度が低くても所有者がいないことを問題にする
+        assert_eq!(issues(&config, "src/a.rs"), (1, 0));
+        assert_eq!(issues(&config, "src/b.rs"), (0, 0));
+        assert_eq!(issues(&config, "docs/c.md"), (0, 1));
+        assert_eq!(issues(&config, "docs/d.md"), (0, 0));
+        assert_eq!(issues(&config, ""), (1, 1));
+
+        let config = OwnersConfig {
+            hot_commits: 2,
+            ..OwnersConfig::default()
+        };
+        assert_eq!(issues(&config, "src/a.rs"), (0, 0));
+    }
+}
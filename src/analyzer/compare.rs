@@ -0,0 +1,136 @@
+use super::git::{CommitRecord, HistoryQuery};
+use super::history::{unix_now, HistoryMetrics, HistorySpec, SECONDS_PER_DAY};
+use super::scanner::{apply_baseline, apply_history, build_tree_from_lines};
+use super::GitAnalyzer;
+use crate::data::FileNode;
+use anyhow::{bail, Result};
+use git2::Oid;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 比較する2時点の指定
+#[derive(Debug, Clone)]
+pub enum CompareSpec {
+    /// `old..new` 形式のリビジョン指定（各時点の変更頻度はその時点から遡った期間で計算）
+    Revisions { old: String, new: String },
+    /// 直近N日間とその前のN日間
+    Periods { days: usize },
+}
+
+impl CompareSpec {
+    /// `v1.0..v2.0` 形式の範囲指定を解析
+    pub fn parse_range(range: &str) -> Result<Self> {
+        match range.split_once("..") {
+            Some((old, new)) if !old.is_empty() && !new.is_empty() => Ok(Self::Revisions {
+                old: old.to_string(),
+                new: new.to_string(),
+            }),
+            _ => bail!("invalid range (expected OLD..NEW): {}", range),
+        }
+    }
+
+    /// 画面に表示する比較内容
+    pub fn label(&self) -> String {
+        match self {
+            Self::Revisions { old, new } => format!("{}..{}", old, new),
+            Self::Periods { days } => format!("last {} days vs previous {} days", days, days),
+        }
+    }
+}
+
+/// ある時点の行数と履歴メトリクス
+struct Snapshot {
+    lines: HashMap<PathBuf, usize>,
+    history: HistoryMetrics,
+    /// 履歴メトリクスを求めたコミット
+    records: Vec<CommitRecord>,
+}
+
+/// 新しい時点のメトリクスで構築したツリーに、古い時点との差分を付与して返す
+/// 履歴メトリクスとそれを求めたコミット（新しい時点）も合わせて返す。各時点の履歴は `history` の条件で走査する
+pub fn build_compare_tree(
+    analyzer: &GitAnalyzer,
+    root: &Path,
+    spec: &CompareSpec,
+    history: &HistoryQuery,
+    history_spec: &HistorySpec,
+) -> Result<(FileNode, HistoryMetrics, Vec<CommitRecord>)> {
+    let (old, new) = match spec {
+        CompareSpec::Revisions { old, new } => {
+            let old = analyzer.resolve_commit(old)?;
+            let new = analyzer.resolve_commit(new)?;
+            (
+                snapshot_at(analyzer, history, history_spec, Some(old), analyzer.commit_time(old)?)?,
+                snapshot_at(analyzer, history, history_spec, Some(new), analyzer.commit_time(new)?)?,
+            )
+        }
+        CompareSpec::Periods { days } => {
+            let now = unix_now();
+            let boundary = now - *days as i64 * SECONDS_PER_DAY;
+            let head = analyzer.resolve_commit("HEAD")?;
+            let history_spec = &HistorySpec {
+                days: *days,
+                ..history_spec.clone()
+            };
+            (
+                snapshot_at(analyzer, history, history_spec, analyzer.last_commit_before(boundary)?, boundary)?,
+                snapshot_at(analyzer, history, history_spec, Some(head), now)?,
+            )
+        }
+    };
+
+    // 古い時点にしか存在しないファイルも0行として含め、削除による減少を集計に反映する
+    let mut lines = new.lines.clone();
+    for path in old.lines.keys() {
+        lines.entry(path.clone()).or_insert(0);
+    }
+
+    let mut tree = build_tree_from_lines(root, &lines);
+    apply_history(&mut tree, root, &new.history);
+    apply_baseline(&mut tree, root, &old.lines, &old.history.frequency);
+
+    Ok((tree, new.history, new.records))
+}
+
+/// `until` 時点のツリー（それ以前の最後のコミットの行数と、遡って `history_spec.days` 日間の履歴メトリクス）
+pub fn build_tree_at(
+    analyzer: &GitAnalyzer,
+    root: &Path,
+    history: &HistoryQuery,
+    history_spec: &HistorySpec,
+    until: i64,
+) -> Result<FileNode> {
+    let commit = analyzer.last_commit_before(until)?;
+    let snapshot = snapshot_at(analyzer, history, history_spec, commit, until)?;
+
+    let mut tree = build_tree_from_lines(root, &snapshot.lines);
+    apply_history(&mut tree, root, &snapshot.history);
+    Ok(tree)
+}
+
+/// `commit` 時点の行数と、`until` から遡って `spec.days` 日間の履歴メトリクス
+/// コミットがない（リポジトリがまだ存在しない）時点は空とする
+fn snapshot_at(
+    analyzer: &GitAnalyzer,
+    history: &HistoryQuery,
+    spec: &HistorySpec,
+    commit: Option<Oid>,
+    until: i64,
+) -> Result<Snapshot> {
+    let Some(commit) = commit else {
+        return Ok(Snapshot {
+            lines: HashMap::new(),
+            history: HistoryMetrics::default(),
+            records: Vec::new(),
+        });
+    };
+
+    let since = until - spec.days as i64 * SECONDS_PER_DAY;
+    let records = analyzer.collect_commits(&history.window(commit, since), |_, _, _| {})?;
+
+    Ok(Snapshot {
+        lines: analyzer.line_counts_at(commit)?,
+        history: spec.compute(&records, until),
+        records,
+    })
+}
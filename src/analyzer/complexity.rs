@@ -0,0 +1,397 @@
+use crate::data::Complexity;
+use std::path::Path;
+
+/// インデント1段とみなす空白の幅（タブは1段）
+const INDENT_WIDTH: usize = 4;
+
+/// 関数の数と循環的複雑度を近似できる言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Language {
+    Rust,
+    Python,
+    Go,
+    /// JavaScript/TypeScript
+    Script,
+    /// C/C++/Java（`if` などの分岐とブロックの形で近似する）
+    CLike,
+}
+
+impl Language {
+    pub(super) fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        match extension {
+            "rs" => Some(Self::Rust),
+            "py" => Some(Self::Python),
+            "go" => Some(Self::Go),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" => Some(Self::Script),
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "java" => Some(Self::CLike),
+            _ => None,
+        }
+    }
+
+    /// 分岐を増やすキーワード
+    fn is_decision(self, word: &str) -> bool {
+        match self {
+            Self::Rust => matches!(word, "if" | "while" | "for"),
+            Self::Python => matches!(word, "if" | "elif" | "for" | "while" | "except" | "and" | "or" | "case"),
+            Self::Go => matches!(word, "if" | "for" | "case"),
+            Self::Script | Self::CLike => matches!(word, "if" | "for" | "while" | "case" | "catch"),
+        }
+    }
+}
+
+/// ブロックコメント（`/* */`）の中かどうかを追跡しながら、コメントだけの行を判定する
+/// 文字列リテラル中の `/*` は区別しない
+#[derive(Debug, Default)]
+pub(super) struct CommentTracker {
+    in_block: bool,
+}
+
+impl CommentTracker {
+    /// 前後の空白を除いた行がコメントだけか（ブロックコメントの後にコードが続く行はコードとする）
+    pub(super) fn is_comment(&mut self, language: Language, line: &str) -> bool {
+        if language == Language::Python {
+            return line.starts_with('#');
+        }
+
+        let rest = if self.in_block {
+            line
+        } else if line.starts_with("//") {
+            return true;
+        } else if let Some(rest) = line.strip_prefix("/*") {
+            rest
+        } else {
+            // 行の途中から始まって閉じないブロックコメント
+            if let Some(start) = line.find("/*") {
+                self.in_block = !line[start + 2..].contains("*/");
+            }
+            return false;
+        };
+
+        match rest.find("*/") {
+            Some(end) => {
+                self.in_block = false;
+                rest[end + 2..].trim().is_empty()
+            }
+            None => {
+                self.in_block = true;
+                true
+            }
+        }
+    }
+}
+
+/// 1行ずつ受け取り複雑さを数える（`LineSplitter` が区切った行を渡す）
+/// 文字列リテラルやブロックコメントの中身は区別しない近似値
+#[derive(Debug)]
+pub struct ComplexityCounter {
+    language: Option<Language>,
+    comments: CommentTracker,
+    indent_sum: usize,
+    indent_max: usize,
+    functions: usize,
+    decisions: usize,
+}
+
+impl ComplexityCounter {
+    /// 拡張子から言語を判定して作成
+    pub fn for_path(path: &Path) -> Self {
+        Self {
+            language: Language::from_path(path),
+            comments: CommentTracker::default(),
+            indent_sum: 0,
+            indent_max: 0,
+            functions: 0,
+            decisions: 0,
+        }
+    }
+
+    /// 複雑さを確定する
+    pub fn finish(self) -> Complexity {
+        let functions = self.language.map(|_| self.functions);
+        // 関数ごとの基本経路1つに分岐の数を足す
+        let cyclomatic = functions.map(|functions| functions.max(1) + self.decisions);
+        Complexity {
+            indent_sum: self.indent_sum,
+            indent_max: self.indent_max,
+            functions,
+            cyclomatic,
+            cyclomatic_max: cyclomatic,
+        }
+    }
+
+    /// 改行を含まない1行を数える
+    pub fn count_line(&mut self, line: &str) {
+        let code = line.trim();
+        if code.is_empty() {
+            return;
+        }
+
+        let width: usize = line
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { INDENT_WIDTH } else { 1 })
+            .sum();
+        let depth = width / INDENT_WIDTH;
+        self.indent_sum += depth;
+        self.indent_max = self.indent_max.max(depth);
+
+        let Some(language) = self.language else {
+            return;
+        };
+        if self.comments.is_comment(language, code) {
+            return;
+        }
+
+        let words: Vec<&str> = code
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|word| !word.is_empty())
+            .collect();
+        let mut decisions = words.iter().filter(|word| language.is_decision(word)).count();
+        if language == Language::Rust {
+            // `impl Trait for Type` と高階トレイト境界の `for<'a>` はループではない
+            let not_loops = if words.contains(&"impl") {
+                words.iter().filter(|&&word| word == "for").count()
+            } else {
+                code.matches("for<").count()
+            };
+            decisions = decisions.saturating_sub(not_loops);
+        }
+        self.decisions += decisions;
+
+        // 論理演算子は短絡評価で分岐する（Pythonは and/or をキーワードとして数えた）
+        if language != Language::Python {
+            self.decisions += count_logical_operators(language, code);
+        }
+
+        match language {
+            // match の各アームを分岐とみなす
+            Language::Rust => {
+                self.decisions += code.matches("=>").count();
+                self.functions += count_definitions(code, "fn");
+            }
+            Language::Python => self.functions += count_definitions(code, "def"),
+            Language::Go => self.functions += count_definitions(code, "func"),
+            Language::Script => {
+                self.decisions += count_ternaries(code);
+                self.functions += count_definitions(code, "function") + code.matches("=>").count();
+            }
+            Language::CLike => {
+                self.decisions += count_ternaries(code);
+                if is_c_function_header(code, &words) {
+                    self.functions += 1;
+                }
+            }
+        }
+    }
+}
+
+/// `keyword 名前` または `keyword(` の形の定義を数える（`fn(` のような型の記述は含めない）
+fn count_definitions(code: &str, keyword: &str) -> usize {
+    code.match_indices(keyword)
+        .filter(|(index, _)| {
+            let before = code[..*index].chars().next_back();
+            let after = &code[index + keyword.len()..];
+            let starts_word = before.is_none_or(|c| !(c.is_alphanumeric() || c == '_'));
+            // Goのメソッド（func (r *T) Name）とJavaScriptの無名関数（function (）も関数とする
+            let defines = match keyword {
+                "fn" | "def" => after.starts_with(' ') && after.trim_start().starts_with(|c: char| c.is_alphabetic() || c == '_'),
+                _ => after.starts_with([' ', '(']),
+            };
+            starts_word && defines
+        })
+        .count()
+}
+
+/// 短絡評価の `&&` と `||` を数える
+/// Rustでは引数のないクロージャの `||` と参照の `&&`（`|&&x|` など）は演算子ではないため、前に値がないものは除く
+fn count_logical_operators(language: Language, code: &str) -> usize {
+    ["&&", "||"]
+        .iter()
+        .map(|operator| {
+            code.match_indices(operator)
+                .filter(|(index, _)| language != Language::Rust || follows_operand(code, *index, operator.len()))
+                .count()
+        })
+        .sum()
+}
+
+/// Rustの `&&`・`||` の前に値（左辺）があるか
+fn follows_operand(code: &str, index: usize, len: usize) -> bool {
+    let before = code[..index].trim_end();
+    if before.is_empty() {
+        // 行頭は前の行から続く条件式の折り返しとみなし、ブロックが続くクロージャ（`|| {`）だけを除く
+        return !code[index + len..].trim_start().starts_with('{');
+    }
+    let last_word = before
+        .rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()
+        .unwrap_or_default();
+    !(before.ends_with(['(', '[', '{', ',', ';', ':', '=', '|', '&', '!', '<'])
+        || before.ends_with("=>")
+        || matches!(last_word, "move" | "return"))
+}
+
+/// 三項演算子の `?` を数える（`?.` や `??` は除く）
+fn count_ternaries(code: &str) -> usize {
+    let bytes = code.as_bytes();
+    (0..bytes.len())
+        .filter(|&i| {
+            bytes[i] == b'?'
+                && bytes.get(i + 1).is_none_or(|&next| next != b'.' && next != b'?')
+                && (i == 0 || bytes[i - 1] != b'?')
+        })
+        .count()
+}
+
+/// C/C++/Javaの関数定義の開始行らしいか（`名前(引数) {` で、制御構文や呼び出し文ではない）
+pub(super) fn is_c_function_header(code: &str, words: &[&str]) -> bool {
+    let Some(paren) = code.find('(') else {
+        return false;
+    };
+    let ends_block = code.ends_with('{') || code.ends_with(')');
+    let first = words.first().copied().unwrap_or_default();
+    let is_statement = matches!(
+        first,
+        "if" | "for" | "while" | "switch" | "catch" | "return" | "else" | "do" | "new" | "throw" | "case"
+    );
+    // 名前の前に戻り値の型があり、代入や文の終わりを含まない
+    let head = code[..paren].trim_end();
+    ends_block
+        && !is_statement
+        && !code.contains(';')
+        && !head.contains('=')
+        && head.split_whitespace().count() >= 2
+        && head.ends_with(|c: char| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complexity(file_name: &str, source: &str) -> Complexity {
+        let mut counter = ComplexityCounter::for_path(Path::new(file_name));
+        for line in source.lines() {
+            counter.count_line(line);
+        }
+        counter.finish()
+    }
+
+    fn is_header(code: &str) -> bool {
+        let words: Vec<&str> = code
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|word| !word.is_empty())
+            .collect();
+        is_c_function_header(code, &words)
+    }
+
+    #[test]
+    fn counts_rust_definitions() {
+        assert_eq!(count_definitions("pub fn parse(input: &str) {", "fn"), 1);
+        assert_eq!(count_definitions("async fn run() {}", "fn"), 1);
+        assert_eq!(count_definitions("let f: fn(i32) -> i32 = double;", "fn"), 0);
+        assert_eq!(count_definitions("let fname = fnv_hash(x);", "fn"), 0);
+    }
+
+    #[test]
+    fn counts_python_definitions() {
+        assert_eq!(count_definitions("def handle(self, event):", "def"), 1);
+        assert_eq!(count_definitions("async def fetch():", "def"), 1);
+        assert_eq!(count_definitions("undefined = default_value", "def"), 0);
+    }
+
+    #[test]
+    fn counts_go_definitions() {
+        assert_eq!(count_definitions("func main() {", "func"), 1);
+        assert_eq!(count_definitions("func (s *Server) Serve() error {", "func"), 1);
+        assert_eq!(count_definitions("handlers := funcs[name]", "func"), 0);
+    }
+
+    #[test]
+    fn counts_script_definitions() {
+        assert_eq!(count_definitions("export function render(props) {", "function"), 1);
+        assert_eq!(count_definitions("setTimeout(function () {", "function"), 1);
+        assert_eq!(count_definitions("const functional = true;", "function"), 0);
+    }
+
+    #[test]
+    fn detects_c_function_headers() {
+        assert!(is_header("int main(int argc, char **argv) {"));
+        assert!(is_header("public static void main(String[] args) {"));
+        assert!(is_header("void Parser::reset()"));
+        assert!(!is_header("if (ready) {"));
+        assert!(!is_header("} else if (ready) {"));
+        assert!(!is_header("return compute(x);"));
+        assert!(!is_header("result = compute(x) {"));
+        assert!(!is_header("compute(x);"));
+        assert!(!is_header("int declared(int x);"));
+    }
+
+    #[test]
+    fn ignores_impl_for_and_higher_ranked_bounds_in_rust() {
+        let source = "impl Display for Node {\n    fn fmt(&self) where F: for<'a> Fn(&'a str) {\n        for child in children {}\n    }\n}\n";
+        let complexity = complexity("lib.rs", source);
+        assert_eq!(complexity.functions, Some(1));
+        // 関数1つ + for 1つ
+        assert_eq!(complexity.cyclomatic, Some(2));
+    }
+
+    #[test]
+    fn ignores_closures_and_references_in_rust_logical_operators() {
+        let source = "fn main() {\n    let f = || 1;\n    let g = move || 2;\n}\nfn x() {}\n";
+        assert_eq!(complexity("lib.rs", source).cyclomatic, Some(2));
+
+        let source = "\
+fn run(items: &[&str], ready: bool, done: bool) {
+    let name = items.iter().find(|&&item| item.is_empty()).unwrap_or_else(|| &\"x\");
+    thread::spawn(move || {
+        work();
+    });
+    call(|| {
+        work();
+    }, || 0);
+    if ready || done && !name.is_empty() {
+        stop();
+    }
+    let waiting = ready
+        || done;
+}
+";
+        // 関数1つ + if 1つ + 二項演算子3つ（`||`、`&&`、折り返した行の `||`）
+        assert_eq!(complexity("lib.rs", source).cyclomatic, Some(5));
+        // Rust以外は `||` をすべて演算子とする
+        assert_eq!(count_logical_operators(Language::Script, "f(|| 1)"), 1);
+    }
+
+    #[test]
+    fn counts_dereference_lines_as_code() {
+        let source = "fn set(ptr: &mut i32, flag: bool) {\n    *ptr = if flag { 1 } else { 2 };\n}\n";
+        assert_eq!(complexity("lib.rs", source).cyclomatic, Some(2));
+    }
+
+    #[test]
+    fn skips_block_comments() {
+        let source = "/*\n * if this and that\n * while (x)\n */\nint run(int x) {\n    *out = x ? 1 : 2;\n}\n";
+        let complexity = complexity("run.c", source);
+        assert_eq!(complexity.functions, Some(1));
+        // 関数1つ + 三項演算子1つ
+        assert_eq!(complexity.cyclomatic, Some(2));
+    }
+
+    #[test]
+    fn counts_python_decisions() {
+        let source = "def check(a, b):\n    # if commented\n    if a and b:\n        return 1\n    elif a:\n        return 2\n";
+        let complexity = complexity("check.py", source);
+        assert_eq!(complexity.functions, Some(1));
+        assert_eq!(complexity.cyclomatic, Some(4));
+    }
+
+    #[test]
+    fn leaves_unknown_languages_without_functions() {
+        let complexity = complexity("notes.txt", "if\n    indented\n        deeper\n");
+        assert_eq!(complexity.functions, None);
+        assert_eq!(complexity.cyclomatic, None);
+        assert_eq!(complexity.indent_sum, 3);
+        assert_eq!(complexity.indent_max, 2);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::data::Coverage;
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// カバレッジレポートを読み込み、ファイルごとの網羅行数を返す（キーはリポジトリルートからの相対パス）
+/// lcov（`.info`）、Cobertura XML、`cargo llvm-cov --json` の形式を内容から判別する
+pub fn load_coverage(report: &Path, root: &Path) -> Result<HashMap<PathBuf, Coverage>> {
+    let content = fs::read_to_string(report)
+        .with_context(|| format!("failed to read coverage report: {}", report.display()))?;
+    let base = report_base(report, root);
+
+    let files = match content.trim_start().chars().next() {
+        Some('{') => parse_llvm_cov(&content)?,
+        Some('<') => parse_cobertura(&content)?,
+        _ if content.lines().any(|line| line.starts_with("SF:")) => parse_lcov(&content),
+        _ => bail!("unrecognized coverage report format: {}", report.display()),
+    };
+
+    let mut coverage: HashMap<PathBuf, Coverage> = HashMap::new();
+    for (path, lines) in files {
+        let Some(relative_path) = resolve_path(&path, root, &base) else {
+            continue;
+        };
+        let entry = coverage.entry(relative_path).or_default();
+        entry.covered += lines.covered;
+        entry.total += lines.total;
+    }
+    Ok(coverage)
+}
+
+/// レポート内の相対パスの基準にするディレクトリ（レポートの場所、わからなければリポジトリルート）
+pub(super) fn report_base(report: &Path, root: &Path) -> PathBuf {
+    report
+        .canonicalize()
+        .ok()
+        .and_then(|report| report.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| root.to_path_buf())
+}
+
+/// レポート内のパスをリポジトリルートからの相対パスにする（リポジトリ外のファイルはNone）
+pub(super) fn resolve_path(path: &Path, root: &Path, base: &Path) -> Option<PathBuf> {
+    if path.is_absolute() {
+        return path.strip_prefix(root).ok().map(Path::to_path_buf);
+    }
+    // レポートの場所を基準に存在すればそれを使い、なければルートからの相対パスとみなす
+    let from_base = base.join(path);
+    match from_base.canonicalize().ok().and_then(|path| path.strip_prefix(root).ok().map(Path::to_path_buf)) {
+        Some(relative_path) => Some(relative_path),
+        None => Some(
+            path.components()
+                .filter(|component| !matches!(component, Component::CurDir))
+                .collect(),
+        ),
+    }
+}
+
+/// 行番号ごとの実行回数から網羅行数を数える
+fn count_hits(hits: &HashMap<usize, u64>) -> Coverage {
+    Coverage {
+        covered: hits.values().filter(|&&count| count > 0).count(),
+        total: hits.len(),
+    }
+}
+
+/// lcov形式（`SF:` から `end_of_record` までが1ファイル、`DA:行番号,実行回数`）
+fn parse_lcov(content: &str) -> Vec<(PathBuf, Coverage)> {
+    let mut files = Vec::new();
+    let mut current: Option<(PathBuf, HashMap<usize, u64>)> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(path) = line.strip_prefix("SF:") {
+            current = Some((PathBuf::from(path), HashMap::new()));
+        } else if let Some(record) = line.strip_prefix("DA:") {
+            let mut fields = record.split(',');
+            let number = fields.next().and_then(|number| number.parse().ok());
+            let count = fields.next().and_then(|count| count.parse::<u64>().ok());
+            if let (Some((_, hits)), Some(number), Some(count)) = (current.as_mut(), number, count) {
+                *hits.entry(number).or_insert(0) += count;
+            }
+        } else if line == "end_of_record" {
+            if let Some((path, hits)) = current.take() {
+                files.push((path, count_hits(&hits)));
+            }
+        }
+    }
+
+    files
+}
+
+/// Cobertura XML（`<class filename=...>` の中の `<line number=... hits=...>`）
+/// 属性だけを読めば足りるため、XMLパーサーを使わずにタグを順に読む
+fn parse_cobertura(content: &str) -> Result<Vec<(PathBuf, Coverage)>> {
+    let tag = Regex::new(r"<(source|class|line)\b([^>]*)>([^<]*)")?;
+    let attribute = Regex::new(r#"(\w+)="([^"]*)""#)?;
+
+    // `<sources>` があれば最初のものをファイル名の基準にする
+    let mut source: Option<PathBuf> = None;
+    let mut files: HashMap<PathBuf, HashMap<usize, u64>> = HashMap::new();
+    let mut current: Option<PathBuf> = None;
+
+    for captures in tag.captures_iter(content) {
+        let attributes: HashMap<&str, &str> = attribute
+            .captures_iter(&captures[2])
+            .filter_map(|captures| Some((captures.get(1)?.as_str(), captures.get(2)?.as_str())))
+            .collect();
+        match &captures[1] {
+            "source" => {
+                let text = captures[3].trim();
+                if source.is_none() && !text.is_empty() {
+                    source = Some(PathBuf::from(text));
+                }
+            }
+            "class" => {
+                current = attributes.get("filename").map(|filename| match &source {
+                    Some(source) if !Path::new(filename).is_absolute() => source.join(filename),
+                    _ => PathBuf::from(filename),
+                });
+            }
+            _ => {
+                let number = attributes.get("number").and_then(|number| number.parse().ok());
+                let count = attributes.get("hits").and_then(|count| count.parse().ok());
+                if let (Some(path), Some(number), Some(count)) = (&current, number, count) {
+                    // `<methods>` の中と外に同じ行が重複して現れる
+                    let hits = files.entry(path.clone()).or_default().entry(number).or_insert(0);
+                    *hits = (*hits).max(count);
+                }
+            }
+        }
+    }
+
+    Ok(files
+        .into_iter()
+        .map(|(path, hits)| (path, count_hits(&hits)))
+        .collect())
+}
+
+/// `cargo llvm-cov --json`（`llvm-cov export` の形式）のうち必要な部分
+#[derive(Deserialize)]
+struct LlvmCovExport {
+    data: Vec<LlvmCovData>,
+}
+
+#[derive(Deserialize)]
+struct LlvmCovData {
+    files: Vec<LlvmCovFile>,
+}
+
+#[derive(Deserialize)]
+struct LlvmCovFile {
+    filename: PathBuf,
+    summary: LlvmCovSummary,
+}
+
+#[derive(Deserialize)]
+struct LlvmCovSummary {
+    lines: LlvmCovCount,
+}
+
+#[derive(Deserialize)]
+struct LlvmCovCount {
+    count: usize,
+    covered: usize,
+}
+
+/// llvm-covのJSON（ファイルごとの集計値をそのまま使う）
+fn parse_llvm_cov(content: &str) -> Result<Vec<(PathBuf, Coverage)>> {
+    let export: LlvmCovExport = serde_json::from_str(content).context("invalid llvm-cov JSON")?;
+    Ok(export
+        .data
+        .into_iter()
+        .flat_map(|data| data.files)
+        .map(|file| {
+            let coverage = Coverage {
+                covered: file.summary.lines.covered,
+                total: file.summary.lines.count,
+            };
+            (file.filename, coverage)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coverage(covered: usize, total: usize) -> Coverage {
+        Coverage { covered, total }
+    }
+
+    #[test]
+    fn parses_lcov_records() {
+        let report = "\
+TN:
+SF:src/lib.rs
+DA:1,3
+DA:2,0
+DA:3,1
+end_of_record
+SF:/repo/src/main.rs
+DA:1,0
+DA:1,2
+end_of_record
+";
+        assert_eq!(
+            parse_lcov(report),
+            vec![
+                (PathBuf::from("src/lib.rs"), coverage(2, 3)),
+                // 同じ行が複数回現れた場合は実行回数を足す
+                (PathBuf::from("/repo/src/main.rs"), coverage(1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_lcov_lines_outside_records() {
+        assert!(parse_lcov("DA:1,1\nend_of_record\n").is_empty());
+    }
+
+    #[test]
+    fn parses_cobertura_classes_relative_to_the_first_source() {
+        let report = r#"<?xml version="1.0" ?>
+<coverage line-rate="0.5">
+  <sources>
+    <source>/repo/src</source>
+    <source>/other</source>
+  </sources>
+  <packages><package name="app"><classes>
+    <class name="lib" filename="lib.rs">
+      <methods><method name="run"><lines><line number="1" hits="2"/></lines></method></methods>
+      <lines>
+        <line number="1" hits="2"/>
+        <line number="2" hits="0"/>
+      </lines>
+    </class>
+    <class name="abs" filename="/elsewhere/abs.rs">
+      <lines><line number="1" hits="1"/></lines>
+    </class>
+  </classes></package></packages>
+</coverage>"#;
+        let mut files = parse_cobertura(report).unwrap();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            files,
+            vec![
+                (PathBuf::from("/elsewhere/abs.rs"), coverage(1, 1)),
+                // `<methods>` の中の重複した行は1行として数える
+                (PathBuf::from("/repo/src/lib.rs"), coverage(1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_cobertura_without_sources() {
+        let report = r#"<coverage><class filename="src/a.py"><line number="3" hits="0"/></class></coverage>"#;
+        assert_eq!(
+            parse_cobertura(report).unwrap(),
+            vec![(PathBuf::from("src/a.py"), coverage(0, 1))]
+        );
+    }
+
+    #[test]
+    fn parses_llvm_cov_summaries() {
+        let report = r#"{"data":[{"files":[{"filename":"/repo/src/lib.rs","summary":{"lines":{"count":10,"covered":7,"percent":70.0}}}]}],"type":"llvm.coverage.json.export"}"#;
+        assert_eq!(
+            parse_llvm_cov(report).unwrap(),
+            vec![(PathBuf::from("/repo/src/lib.rs"), coverage(7, 10))]
+        );
+    }
+
+    #[test]
+    fn resolves_paths_against_the_report_or_the_root() {
+        let root = std::env::temp_dir().join(format!("heatree-coverage-{}", std::process::id()));
+        let base = root.join("target/coverage");
+        fs::create_dir_all(&base).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        let root = root.canonicalize().unwrap();
+        let base = base.canonicalize().unwrap();
+
+        assert_eq!(resolve_path(&root.join("src/lib.rs"), &root, &base), Some(PathBuf::from("src/lib.rs")));
+        assert_eq!(resolve_path(Path::new("/outside/lib.rs"), &root, &base), None);
+        // レポートの場所から辿れるパス
+        assert_eq!(resolve_path(Path::new("../../src/lib.rs"), &root, &base), Some(PathBuf::from("src/lib.rs")));
+        // レポートの場所にないパスはルートからの相対パスとみなす
+        assert_eq!(resolve_path(Path::new("./src/main.rs"), &root, &base), Some(PathBuf::from("src/main.rs")));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,61 @@
+use super::git::CommitRecord;
+use super::loader::LoadEvent;
+use super::GitAnalyzer;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+
+/// 詳細パネルの要求
+pub struct DetailRequest {
+    /// ファイルの絶対パス
+    pub path: PathBuf,
+    /// blameも求めるか（関数ごとの変更回数だけを求め直す場合はfalse）
+    pub blame: bool,
+    /// 関数ごとの変更回数で数えるコミット（変更頻度を求めたもの）
+    pub records: Arc<Vec<CommitRecord>>,
+}
+
+/// 詳細パネル用にblameと関数ごとの変更回数を求めるスレッドを開始し、要求の送信先を返す
+/// `rev` が指定されていればそのリビジョン、なければHEADのファイルを対象にする
+pub fn spawn_detail(root: PathBuf, rev: Option<String>, tx: Sender<LoadEvent>) -> Sender<DetailRequest> {
+    let (request_tx, request_rx) = mpsc::channel::<DetailRequest>();
+
+    thread::spawn(move || {
+        let Ok(analyzer) = GitAnalyzer::new(&root) else {
+            return;
+        };
+
+        while let Ok(mut request) = request_rx.recv() {
+            // 選択を素早く動かした場合は最後に選択したファイルだけを処理する
+            while let Ok(latest) = request_rx.try_recv() {
+                request = latest;
+            }
+
+            let relative_path = request.path.strip_prefix(&root).unwrap_or(&request.path).to_path_buf();
+            let commit = analyzer
+                .resolve_commit(rev.as_deref().unwrap_or("HEAD"))
+                .map_err(|err| err.to_string());
+
+            if request.blame {
+                let result = commit
+                    .clone()
+                    .and_then(|commit| analyzer.blame_at(commit, &relative_path).map_err(|err| err.to_string()));
+                if tx.send(LoadEvent::Blame(request.path.clone(), result)).is_err() {
+                    return;
+                }
+            }
+
+            let result = commit.and_then(|commit| {
+                analyzer
+                    .function_hotspots_at(commit, &relative_path, &request.records)
+                    .map_err(|err| err.to_string())
+            });
+            if tx.send(LoadEvent::Functions(request.path, result)).is_err() {
+                return;
+            }
+        }
+    });
+
+    request_tx
+}
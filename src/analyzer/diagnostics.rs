@@ -0,0 +1,350 @@
+use super::coverage::{report_base, resolve_path};
+use crate::data::Diagnostics;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 診断結果の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Cargo,
+    Sarif,
+    Eslint,
+}
+
+/// 診断結果のファイルを読み込み、ファイルごとのエラー・警告の数を返す（キーはリポジトリルートからの相対パス）
+/// `cargo clippy --message-format=json`（`cargo build` も同じ形式）、SARIF、ESLintのJSONを拡張子かJSONの最上位の形から判別する
+/// 複数のファイルを指定した場合は合計する
+pub fn load_diagnostics(reports: &[PathBuf], root: &Path) -> Result<HashMap<PathBuf, Diagnostics>> {
+    let mut diagnostics: HashMap<PathBuf, Diagnostics> = HashMap::new();
+
+    for report in reports {
+        let content = fs::read_to_string(report)
+            .with_context(|| format!("failed to read diagnostics: {}", report.display()))?;
+        let content = content.trim_start_matches('\u{feff}');
+        let base = report_base(report, root);
+
+        let files = match detect_format(report, content) {
+            Some(Format::Eslint) => parse_eslint(content)?,
+            Some(Format::Sarif) => parse_sarif(content)?,
+            Some(Format::Cargo) => parse_cargo(content),
+            None => bail!("unrecognized diagnostics format: {}", report.display()),
+        };
+
+        for (path, counts) in files {
+            let Some(relative_path) = resolve_path(&path, root, &base) else {
+                continue;
+            };
+            let entry = diagnostics.entry(relative_path).or_default();
+            entry.errors += counts.errors;
+            entry.warnings += counts.warnings;
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+/// 拡張子が `.sarif` ならSARIF、それ以外はファイル全体を1つのJSONとして読んで最上位の形で判別する
+/// 配列はESLint、`$schema` か `runs` を持つオブジェクトはSARIF、1つのJSONとして読めなければ1行1メッセージのcargoとみなす
+fn detect_format(report: &Path, content: &str) -> Option<Format> {
+    let name = report.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".sarif") || name.ends_with(".sarif.json") {
+        return Some(Format::Sarif);
+    }
+
+    match serde_json::from_str::<serde_json::Value>(content) {
+        Ok(serde_json::Value::Array(_)) => Some(Format::Eslint),
+        Ok(serde_json::Value::Object(object)) if object.contains_key("$schema") || object.contains_key("runs") => {
+            Some(Format::Sarif)
+        }
+        Ok(serde_json::Value::Object(_)) => Some(Format::Cargo),
+        Ok(_) => None,
+        Err(_) if content.lines().any(|line| line.trim_start().starts_with('{')) => Some(Format::Cargo),
+        Err(_) => None,
+    }
+}
+
+/// 重大度の名前から数える先を決める（note・help などは数えない）
+fn count_level(counts: &mut Diagnostics, level: &str) {
+    match level {
+        "error" | "error: internal compiler error" => counts.errors += 1,
+        "warning" => counts.warnings += 1,
+        _ => {}
+    }
+}
+
+/// cargoのJSONメッセージのうち必要な部分
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<CompilerMessage>,
+}
+
+#[derive(Deserialize)]
+struct CompilerMessage {
+    level: String,
+    #[serde(default)]
+    spans: Vec<CompilerSpan>,
+}
+
+#[derive(Deserialize)]
+struct CompilerSpan {
+    file_name: PathBuf,
+    is_primary: bool,
+}
+
+/// cargoのJSON（1行に1メッセージ、主要な位置のファイルに数える）
+/// ビルドの進捗など診断以外の行や、位置のない要約（"N warnings emitted"）は読み飛ばす
+fn parse_cargo(content: &str) -> Vec<(PathBuf, Diagnostics)> {
+    let mut files: HashMap<PathBuf, Diagnostics> = HashMap::new();
+
+    for line in content.lines() {
+        let Ok(message) = serde_json::from_str::<CargoMessage>(line) else {
+            continue;
+        };
+        let Some(message) = message.message.filter(|_| message.reason == "compiler-message") else {
+            continue;
+        };
+        if let Some(span) = message.spans.iter().find(|span| span.is_primary) {
+            count_level(files.entry(span.file_name.clone()).or_default(), &message.level);
+        }
+    }
+
+    files.into_iter().collect()
+}
+
+/// SARIFのうち必要な部分
+#[derive(Deserialize)]
+struct SarifLog {
+    runs: Vec<SarifRun>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRun {
+    #[serde(default)]
+    results: Vec<SarifResult>,
+    /// `uriBaseId` の名前ごとの基準のURI
+    #[serde(default)]
+    original_uri_base_ids: HashMap<String, SarifArtifactLocation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    level: Option<String>,
+    #[serde(default)]
+    locations: Vec<SarifLocation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    physical_location: Option<SarifPhysicalLocation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifPhysicalLocation {
+    artifact_location: SarifArtifactLocation,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifArtifactLocation {
+    uri: Option<String>,
+    uri_base_id: Option<String>,
+}
+
+/// `uriBaseId` を辿る深さの上限（循環した定義で止まらなくならないように）
+const MAX_URI_BASE_DEPTH: usize = 8;
+
+/// SARIF（結果ごとに最初の位置のファイルに数える、重大度の省略時は警告）
+fn parse_sarif(content: &str) -> Result<Vec<(PathBuf, Diagnostics)>> {
+    let log: SarifLog = serde_json::from_str(content).context("invalid SARIF")?;
+    let mut files: HashMap<PathBuf, Diagnostics> = HashMap::new();
+
+    for run in log.runs {
+        for result in run.results {
+            let Some(location) = result
+                .locations
+                .into_iter()
+                .find_map(|location| location.physical_location)
+            else {
+                continue;
+            };
+            let Some(uri) = absolute_uri(&location.artifact_location, &run.original_uri_base_ids, 0) else {
+                continue;
+            };
+            count_level(
+                files.entry(uri_to_path(&uri)).or_default(),
+                result.level.as_deref().unwrap_or("warning"),
+            );
+        }
+    }
+
+    Ok(files.into_iter().collect())
+}
+
+/// 相対URIに `uriBaseId` の基準のURIを付ける（基準が定義されていなければ相対URIのまま）
+fn absolute_uri(
+    location: &SarifArtifactLocation,
+    bases: &HashMap<String, SarifArtifactLocation>,
+    depth: usize,
+) -> Option<String> {
+    let uri = location.uri.as_deref()?;
+    if uri.starts_with("file:") || uri.starts_with('/') {
+        return Some(uri.to_string());
+    }
+    let base = location
+        .uri_base_id
+        .as_ref()
+        .and_then(|id| bases.get(id))
+        .filter(|_| depth < MAX_URI_BASE_DEPTH)
+        .and_then(|base| absolute_uri(base, bases, depth + 1));
+    Some(match base {
+        Some(base) => format!("{}/{}", base.trim_end_matches('/'), uri),
+        None => uri.to_string(),
+    })
+}
+
+/// `file:` のURIや相対URIをパスにする（パーセントエンコードは元に戻す）
+fn uri_to_path(uri: &str) -> PathBuf {
+    let path = uri
+        .strip_prefix("file://localhost")
+        .or_else(|| uri.strip_prefix("file://"))
+        .or_else(|| uri.strip_prefix("file:"))
+        .unwrap_or(uri);
+    PathBuf::from(percent_decode(path))
+}
+
+/// `%XX` をバイトに戻す（不正な並びはそのまま残す）
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// ESLintのJSON（`--format json`）のうち必要な部分
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EslintResult {
+    file_path: PathBuf,
+    error_count: usize,
+    warning_count: usize,
+}
+
+/// ESLintのJSON（ファイルごとの集計値をそのまま使う）
+fn parse_eslint(content: &str) -> Result<Vec<(PathBuf, Diagnostics)>> {
+    let results: Vec<EslintResult> = serde_json::from_str(content).context("invalid ESLint JSON")?;
+    Ok(results
+        .into_iter()
+        .map(|result| {
+            let counts = Diagnostics {
+                errors: result.error_count,
+                warnings: result.warning_count,
+            };
+            (result.file_path, counts)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(errors: usize, warnings: usize) -> Diagnostics {
+        Diagnostics { errors, warnings }
+    }
+
+    fn sorted(mut files: Vec<(PathBuf, Diagnostics)>) -> Vec<(PathBuf, Diagnostics)> {
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        files
+    }
+
+    #[test]
+    fn detects_formats_from_the_extension_or_the_top_level_shape() {
+        let sarif = "{\n  \"version\": \"2.1.0\",\n  \"runs\": []\n}";
+        assert_eq!(detect_format(Path::new("out.json"), sarif), Some(Format::Sarif));
+        assert_eq!(detect_format(Path::new("out.sarif"), "{}"), Some(Format::Sarif));
+        assert_eq!(detect_format(Path::new("out.json"), "{\"$schema\": \"sarif\"}"), Some(Format::Sarif));
+        assert_eq!(detect_format(Path::new("eslint.json"), "[\n  {}\n]"), Some(Format::Eslint));
+        // SARIFの中に `reason` という文字列があっても判別は変わらない
+        let messages = "{\"reason\":\"compiler-artifact\"}\n{\"reason\":\"compiler-message\"}\n";
+        assert_eq!(detect_format(Path::new("clippy.json"), messages), Some(Format::Cargo));
+        assert_eq!(detect_format(Path::new("clippy.json"), "{\"reason\":\"build-finished\"}"), Some(Format::Cargo));
+        assert_eq!(detect_format(Path::new("notes.txt"), "hello"), None);
+    }
+
+    #[test]
+    fn counts_cargo_messages_on_the_primary_span() {
+        let content = r#"{"reason":"compiler-artifact","target":{}}
+{"reason":"compiler-message","message":{"level":"warning","spans":[{"file_name":"src/a.rs","is_primary":false},{"file_name":"src/b.rs","is_primary":true}]}}
+{"reason":"compiler-message","message":{"level":"error","spans":[{"file_name":"src/b.rs","is_primary":true}]}}
+{"reason":"compiler-message","message":{"level":"warning","spans":[]}}
+"#;
+        assert_eq!(parse_cargo(content), vec![(PathBuf::from("src/b.rs"), counts(1, 1))]);
+    }
+
+    #[test]
+    fn resolves_sarif_uris_against_uri_base_ids() {
+        let content = r#"{
+  "runs": [{
+    "originalUriBaseIds": {
+      "SRCROOT": { "uri": "file:///repo/" },
+      "LIB": { "uri": "lib", "uriBaseId": "SRCROOT" }
+    },
+    "results": [
+      { "level": "error", "locations": [{ "physicalLocation": { "artifactLocation": { "uri": "src/a%20b.rs", "uriBaseId": "SRCROOT" } } }] },
+      { "locations": [{ "physicalLocation": { "artifactLocation": { "uri": "c.rs", "uriBaseId": "LIB" } } }] },
+      { "level": "note", "locations": [{ "physicalLocation": { "artifactLocation": { "uri": "file:///repo/d.rs" } } }] },
+      { "level": "warning", "locations": [{ "physicalLocation": { "artifactLocation": { "uri": "e.rs", "uriBaseId": "UNKNOWN" } } }] }
+    ]
+  }]
+}"#;
+        assert_eq!(
+            sorted(parse_sarif(content).unwrap()),
+            vec![
+                (PathBuf::from("/repo/d.rs"), counts(0, 0)),
+                (PathBuf::from("/repo/lib/c.rs"), counts(0, 1)),
+                (PathBuf::from("/repo/src/a b.rs"), counts(1, 0)),
+                (PathBuf::from("e.rs"), counts(0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_uris_to_paths() {
+        assert_eq!(uri_to_path("file:///repo/a%2Bb.rs"), PathBuf::from("/repo/a+b.rs"));
+        assert_eq!(uri_to_path("file://localhost/repo/a.rs"), PathBuf::from("/repo/a.rs"));
+        assert_eq!(uri_to_path("src/%E3%81%82.rs"), PathBuf::from("src/あ.rs"));
+        assert_eq!(uri_to_path("src/100%.rs"), PathBuf::from("src/100%.rs"));
+        assert_eq!(uri_to_path("src/%+1.rs"), PathBuf::from("src/%+1.rs"));
+    }
+
+    #[test]
+    fn reads_eslint_totals() {
+        let content = r#"[{"filePath":"/repo/a.js","errorCount":2,"warningCount":1,"messages":[]}]"#;
+        assert_eq!(parse_eslint(content).unwrap(), vec![(PathBuf::from("/repo/a.js"), counts(2, 1))]);
+    }
+}
@@ -0,0 +1,233 @@
+use super::git::CommitRecord;
+use super::GitAnalyzer;
+use crate::config::FiltersConfig;
+use anyhow::{Context, Result};
+use git2::Oid;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// 完全なコミットIDの長さ（16進数の桁数）
+const GIT_OID_HEXSZ: usize = 40;
+
+/// 数えないコミットの条件
+/// 走査したコミットすべてに適用されるため、変更頻度・アクティビティ・修正コミット・年齢のいずれにも反映される
+/// （blameはlibgit2がignore-revsに対応していないため対象外）
+#[derive(Debug, Clone, Default)]
+pub struct CommitFilter {
+    /// いずれかに一致する作者（`名前 <メール>`）のコミットだけを数える（空ならすべて）
+    include_authors: Vec<Regex>,
+    exclude_authors: Vec<Regex>,
+    /// いずれかに一致するメッセージのコミットだけを数える（空ならすべて）
+    include_messages: Vec<Regex>,
+    exclude_messages: Vec<Regex>,
+    /// 数えないファイル（リポジトリルートからの相対パスに対する正規表現）
+    exclude_paths: Vec<Regex>,
+    /// 無視するコミットID（`.git-blame-ignore-revs` 形式のファイルから読む）
+    ignore_revs: HashSet<String>,
+    /// これより多くのファイルを変更したコミットは一括整形などとみなして数えない
+    max_files: Option<usize>,
+}
+
+impl CommitFilter {
+    /// 設定から作成（ignore-revsのファイルはリポジトリルートからの相対パス）
+    pub fn new(config: &FiltersConfig, root: &Path, analyzer: &GitAnalyzer) -> Result<Self> {
+        let mut ignore_revs = HashSet::new();
+        if let Some(file) = &config.ignore_revs_file {
+            let path = root.join(file);
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            for line in content.lines() {
+                // `#` 以降はコメント
+                let rev = line.split('#').next().unwrap_or_default().trim();
+                if rev.is_empty() {
+                    continue;
+                }
+                // 完全なコミットIDはそのまま使い、短縮形などは解決できたものだけを使う
+                // （git blameと同じく、リポジトリにないコミットは無視する）
+                let oid = match Oid::from_str(rev) {
+                    Ok(oid) if rev.len() == GIT_OID_HEXSZ => Some(oid),
+                    _ => analyzer.resolve_commit(rev).ok(),
+                };
+                if let Some(oid) = oid {
+                    ignore_revs.insert(oid.to_string());
+                }
+            }
+        }
+
+        Ok(Self {
+            include_authors: compile(&config.include_authors)?,
+            exclude_authors: compile(&config.exclude_authors)?,
+            include_messages: compile(&config.include_messages)?,
+            exclude_messages: compile(&config.exclude_messages)?,
+            exclude_paths: compile(&config.exclude_paths)?,
+            ignore_revs,
+            max_files: config.max_files,
+        })
+    }
+
+    /// メッセージで絞り込むか（メッセージはキャッシュに持たないため、必要な場合だけ読む）
+    pub(super) fn filters_messages(&self) -> bool {
+        !self.include_messages.is_empty() || !self.exclude_messages.is_empty()
+    }
+
+    /// 条件に従ってレコードを変換（数えない場合はNone）
+    /// `message` はメッセージで絞り込まない場合は空でよい
+    pub(super) fn apply(&self, oid: &str, mut record: CommitRecord, message: &str) -> Option<CommitRecord> {
+        if self.ignore_revs.contains(oid) {
+            return None;
+        }
+
+        let author = format!("{} <{}>", record.author, record.email);
+        if !matches_filter(&self.include_authors, &self.exclude_authors, &author)
+            || !matches_filter(&self.include_messages, &self.exclude_messages, message)
+        {
+            return None;
+        }
+
+        // 最初のコミットはすべてのファイルを追加するため、ファイル数では除かない（年齢の起点になる）
+        if !record.is_root() && self.max_files.is_some_and(|max| record.files.len() > max) {
+            return None;
+        }
+
+        if !self.exclude_paths.is_empty() {
+            record.files.retain(|path| {
+                let path = path.to_string_lossy();
+                !self.exclude_paths.iter().any(|pattern| pattern.is_match(&path))
+            });
+        }
+        Some(record)
+    }
+}
+
+/// 含める条件（空ならすべて）に一致し、除外する条件に一致しない
+fn matches_filter(include: &[Regex], exclude: &[Regex], value: &str) -> bool {
+    (include.is_empty() || include.iter().any(|pattern| pattern.is_match(value)))
+        && !exclude.iter().any(|pattern| pattern.is_match(value))
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).with_context(|| format!("invalid filter pattern: {}", pattern)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::test_repo::TestRepo;
+    use std::path::PathBuf;
+
+    fn patterns(patterns: &[&str]) -> Vec<Regex> {
+        compile(&patterns.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    fn record(author: &str, email: &str, files: &[&str]) -> CommitRecord {
+        CommitRecord {
+            id: Oid::zero(),
+            time: 0,
+            parent_count: 1,
+            files: files.iter().map(PathBuf::from).collect(),
+            merge_files: Vec::new(),
+            fix: false,
+            author: author.to_string(),
+            email: email.to_string(),
+        }
+    }
+
+    /// 数える場合は残ったファイル
+    fn apply(filter: &CommitFilter, record: CommitRecord, message: &str) -> Option<Vec<String>> {
+        filter.apply("0", record, message).map(|record| {
+            record
+                .files
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn matches_authors_by_name_and_email() {
+        let filter = CommitFilter {
+            include_authors: patterns(&["@example\\.com>$"]),
+            exclude_authors: patterns(&["\\[bot\\]"]),
+            ..CommitFilter::default()
+        };
+        assert!(apply(&filter, record("Alice", "alice@example.com", &["a.rs"]), "").is_some());
+        assert!(apply(&filter, record("Alice", "alice@example.org", &["a.rs"]), "").is_none());
+        assert!(apply(&filter, record("deps[bot]", "bot@example.com", &["a.rs"]), "").is_none());
+    }
+
+    #[test]
+    fn matches_messages() {
+        let filter = CommitFilter {
+            include_messages: patterns(&["^(feat|fix)"]),
+            exclude_messages: patterns(&["\\[skip stats\\]"]),
+            ..CommitFilter::default()
+        };
+        assert!(filter.filters_messages());
+        assert!(!CommitFilter::default().filters_messages());
+        let commit = || record("dev", "dev@example.com", &["a.rs"]);
+        assert!(apply(&filter, commit(), "fix: crash").is_some());
+        assert!(apply(&filter, commit(), "chore: bump").is_none());
+        assert!(apply(&filter, commit(), "feat: big import [skip stats]").is_none());
+    }
+
+    #[test]
+    fn counts_files_before_excluding_paths() {
+        let filter = CommitFilter {
+            exclude_paths: patterns(&["^vendor/"]),
+            max_files: Some(2),
+            ..CommitFilter::default()
+        };
+        // 除外するパスを除けば2ファイルでも、変更したファイル数で一括変更とみなす
+        let bulk = record("dev", "dev@example.com", &["src/a.rs", "vendor/x.rs", "vendor/y.rs"]);
+        assert_eq!(apply(&filter, bulk, ""), None);
+        let small = record("dev", "dev@example.com", &["src/a.rs", "vendor/x.rs"]);
+        assert_eq!(apply(&filter, small, ""), Some(vec!["src/a.rs".to_string()]));
+        // 最初のコミットはファイル数では除かない
+        let root = CommitRecord {
+            parent_count: 0,
+            ..record("dev", "dev@example.com", &["a.rs", "b.rs", "c.rs"])
+        };
+        assert_eq!(apply(&filter, root, "").map(|files| files.len()), Some(3));
+    }
+
+    #[test]
+    fn reads_ignore_revs_and_skips_unknown_revisions() {
+        let mut repo = TestRepo::new("filter-ignore-revs");
+        let reformat = repo.commit("a.rs", "a\n");
+        let rename = repo.commit("b.rs", "b\n");
+        let kept = repo.commit("c.rs", "c\n");
+        let short = &rename.to_string()[..7];
+        let content = format!(
+            "# Reformat\n{}\n\n{}  # rename\n{}\nnot-a-revision\n",
+            reformat,
+            short,
+            "0123456789abcdef0123456789abcdef01234567"
+        );
+        fs::write(repo.dir.join(".git-blame-ignore-revs"), content).unwrap();
+
+        let config = FiltersConfig {
+            ignore_revs_file: Some(PathBuf::from(".git-blame-ignore-revs")),
+            ..FiltersConfig::default()
+        };
+        let analyzer = GitAnalyzer::new(&repo.dir).unwrap();
+        let filter = CommitFilter::new(&config, &repo.dir, &analyzer).unwrap();
+
+        // リポジトリにない完全なコミットIDもそのまま持つが、一致するコミットはない
+        let expected: HashSet<String> = [
+            reformat.to_string(),
+            rename.to_string(),
+            "0123456789abcdef0123456789abcdef01234567".to_string(),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(filter.ignore_revs, expected);
+        let commit = record("dev", "dev@example.com", &["a.rs"]);
+        assert!(filter.apply(&reformat.to_string(), commit.clone(), "").is_none());
+        assert!(filter.apply(&kept.to_string(), commit, "").is_some());
+    }
+}
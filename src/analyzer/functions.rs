@@ -0,0 +1,568 @@
+use super::complexity::{is_c_function_header, CommentTracker, Language};
+use super::git::CommitRecord;
+use anyhow::{Context, Result};
+use git2::{Delta, DiffOptions, Oid, Patch, Repository};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// 関数に含まれない行をまとめた名前
+const TOP_LEVEL: &str = "(top level)";
+
+/// ファイル内の関数ごとの変更回数
+#[derive(Debug, Clone, Default)]
+pub struct FunctionHotspots {
+    /// 期間内にファイルを変更したコミット数
+    pub commits: usize,
+    /// 関数（とimplブロック・クラス）ごとの変更回数（多い順）
+    pub functions: Vec<FunctionHotspot>,
+}
+
+/// 1つの関数の変更回数
+#[derive(Debug, Clone)]
+pub struct FunctionHotspot {
+    pub name: String,
+    /// 現在のファイルでの開始行（1始まり、関数の外の行は0）
+    pub line: usize,
+    /// 変更した行がこの関数に含まれていたコミットの数
+    pub changes: usize,
+}
+
+/// 関数の行範囲（0始まり、`end` を含む）
+#[derive(Debug, Clone)]
+struct FunctionSpan {
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+/// コミット時点のファイルを関数に分け、`records` の各コミットの変更がどの関数に入ったかを数える
+/// 変更行は第1親との差分を新しい順にたどり、後のコミットの差分で現在の行番号に読み替える
+/// `records` にないコミット（フィルタで除いたものなど）も行番号の読み替えには使うが、数えない。
+/// 第1親をたどって到達しない `records` のコミット（マージしたブランチ側のコミット）は数えられない
+pub(super) fn hotspots(
+    repo: &Repository,
+    commit: Oid,
+    relative_path: &Path,
+    records: &[CommitRecord],
+) -> Result<FunctionHotspots> {
+    let counted: HashSet<Oid> = records.iter().map(|record| record.id).collect();
+    let Some(oldest) = records.iter().map(|record| record.time).min() else {
+        return Ok(FunctionHotspots::default());
+    };
+
+    let tree = repo.find_commit(commit)?.tree()?;
+    let entry = tree
+        .get_path(relative_path)
+        .with_context(|| format!("not found at this revision: {}", relative_path.display()))?;
+    let blob = repo.find_blob(entry.id())?;
+    if blob.is_binary() {
+        return Ok(FunctionHotspots::default());
+    }
+    let content = String::from_utf8_lossy(blob.content());
+    let spans = find_functions(relative_path, &content);
+
+    // 現在の行ごとに、それを含む最も内側の関数（なければ末尾の「関数の外」）
+    let line_count = content.lines().count();
+    let mut owner = vec![spans.len(); line_count];
+    for (index, span) in spans.iter().enumerate() {
+        // 外側の関数から順に並んでいるので、後から書いた内側の関数が優先される
+        for line in owner.iter_mut().take(span.end + 1).skip(span.start) {
+            *line = index;
+        }
+    }
+
+    let mut changes = vec![0usize; spans.len() + 1];
+    let mut commits = 0;
+    // 各コミット時点の行番号から現在の行番号への対応（削除済みの行はNone）
+    let mut current_lines: Vec<Option<usize>> = (0..line_count).map(Some).collect();
+    let mut options = DiffOptions::new();
+    options.pathspec(relative_path).context_lines(0);
+
+    let mut next = Some(repo.find_commit(commit)?);
+    while let Some(commit) = next.take() {
+        if commit.time().seconds() < oldest {
+            break;
+        }
+        let parent = commit.parent(0).ok();
+        let parent_tree = parent.as_ref().map(|parent| parent.tree()).transpose()?;
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut options))?;
+
+        // ファイルを変更していないコミットは差分が空になる
+        let patch = match diff.deltas().len() {
+            0 => None,
+            _ => Patch::from_diff(&diff, 0)?,
+        };
+        if let Some(patch) = patch {
+            let status = patch.delta().status();
+            let old_lines = match patch.delta().old_file().id() {
+                id if id.is_zero() => 0,
+                id => count_lines(repo.find_blob(id)?.content()),
+            };
+
+            let mut touched = HashSet::new();
+            let mut parent_lines = Vec::with_capacity(old_lines);
+            let mut new_index = 0;
+            for hunk_index in 0..patch.num_hunks() {
+                let (hunk, _) = patch.hunk(hunk_index)?;
+                let (old_start, new_start) = (hunk.old_start() as usize, hunk.new_start() as usize);
+                let (old_count, new_count) = (hunk.old_lines() as usize, hunk.new_lines() as usize);
+                // 行を追加した範囲は開始行から、削除だけの場合は直前の行の後に入る
+                let hunk_begin = if new_count == 0 { new_start } else { new_start - 1 };
+                let old_begin = if old_count == 0 { old_start } else { old_start - 1 };
+
+                while new_index < hunk_begin && parent_lines.len() < old_begin {
+                    parent_lines.push(current_lines.get(new_index).copied().flatten());
+                    new_index += 1;
+                }
+                if new_count == 0 {
+                    // 削除した位置の前後の行が属する関数を変更したとみなす
+                    let near = new_start.saturating_sub(1).min(current_lines.len().saturating_sub(1));
+                    touched.extend(current_lines.get(near).copied().flatten().map(|line| owner[line]));
+                } else {
+                    let range = hunk_begin..(hunk_begin + new_count).min(current_lines.len());
+                    touched.extend(current_lines[range].iter().flatten().map(|&line| owner[line]));
+                }
+                // 書き換えた行は変更前の行を変更後の行に順に対応させ、以前の変更も同じ関数に数える
+                // （削除だけの行は現在のファイルに残っていない）
+                for offset in 0..old_count {
+                    let line = match new_count {
+                        0 => None,
+                        _ => current_lines.get(hunk_begin + offset.min(new_count - 1)).copied().flatten(),
+                    };
+                    parent_lines.push(line);
+                }
+                new_index = hunk_begin + new_count;
+            }
+            while parent_lines.len() < old_lines {
+                parent_lines.push(current_lines.get(new_index).copied().flatten());
+                new_index += 1;
+            }
+
+            if counted.contains(&commit.id()) {
+                commits += 1;
+                for index in touched {
+                    changes[index] += 1;
+                }
+            }
+            // 追加されたコミットより前には遡らない（名前の変更も追加として扱う）
+            if status == Delta::Added {
+                break;
+            }
+            current_lines = parent_lines;
+        }
+
+        next = parent;
+    }
+
+    let mut functions: Vec<FunctionHotspot> = spans
+        .iter()
+        .map(|span| FunctionHotspot {
+            name: span.name.clone(),
+            line: span.start + 1,
+            changes: 0,
+        })
+        .chain(std::iter::once(FunctionHotspot {
+            name: TOP_LEVEL.to_string(),
+            line: 0,
+            changes: 0,
+        }))
+        .zip(changes)
+        .map(|(function, changes)| FunctionHotspot { changes, ..function })
+        .filter(|function| function.changes > 0)
+        .collect();
+    functions.sort_by(|a, b| b.changes.cmp(&a.changes).then_with(|| a.line.cmp(&b.line)));
+
+    Ok(FunctionHotspots { commits, functions })
+}
+
+fn count_lines(content: &[u8]) -> usize {
+    let newlines = content.iter().filter(|&&b| b == b'\n').count();
+    newlines + usize::from(content.last().is_some_and(|&b| b != b'\n'))
+}
+
+/// ファイルの関数・implブロック・クラスの行範囲を求める（外側のものが先に並ぶ）
+/// 対応していない言語は空を返す
+fn find_functions(path: &Path, content: &str) -> Vec<FunctionSpan> {
+    match Language::from_path(path) {
+        None => Vec::new(),
+        Some(Language::Python) => find_indented(content),
+        Some(language) => find_braced(language, content),
+    }
+}
+
+/// 定義の行から名前を取り出す
+fn definition_name(language: Language, code: &str) -> Option<String> {
+    let words: Vec<&str> = code
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .collect();
+    let after = |keyword: &str| {
+        words
+            .iter()
+            .position(|&word| word == keyword)
+            .and_then(|index| words.get(index + 1))
+            .map(|name| name.to_string())
+    };
+
+    match language {
+        Language::Rust => {
+            if words.first().is_some_and(|&word| word == "impl" || word == "trait") || after("trait").is_some() {
+                // `impl<T> Trait for Type {` の本体を除いた部分を名前にする
+                return Some(code.trim_end_matches('{').trim().to_string());
+            }
+            // `fn(u8)` のような関数ポインタ型は定義ではない
+            let (index, _) = code
+                .match_indices("fn ")
+                .find(|(index, _)| code[..*index].ends_with([' ', ')']) || *index == 0)?;
+            let name: String = code[index + 3..]
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            (!name.is_empty()).then_some(name)
+        }
+        Language::Go => {
+            let rest = code.strip_prefix("func ")?;
+            // メソッドはレシーバーの後の名前
+            let rest = match rest.strip_prefix('(') {
+                Some(receiver) => receiver.split_once(')')?.1,
+                None => rest,
+            };
+            let name: String = rest.trim_start().chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+            (!name.is_empty()).then_some(name)
+        }
+        Language::Script => {
+            if let Some(name) = after("function").or_else(|| after("class")) {
+                return Some(name);
+            }
+            // `const name = (...) => {` の形の関数
+            let (head, _) = code.split_once('=')?;
+            let mut head = head.split_whitespace();
+            let declares = matches!(head.next(), Some("const" | "let" | "var"));
+            let name = head.next().filter(|name| name.chars().all(|c| c.is_alphanumeric() || c == '_'))?;
+            (declares && code.contains("=>")).then(|| name.to_string())
+        }
+        Language::CLike => {
+            if let Some(name) = after("class").or_else(|| after("struct")).filter(|_| !code.ends_with(';')) {
+                return Some(name);
+            }
+            is_c_function_header(code, &words).then(|| {
+                let head = code[..code.find('(').unwrap_or(code.len())].trim_end();
+                head.rsplit(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':')).next().unwrap_or(head).to_string()
+            })
+        }
+        Language::Python => None,
+    }
+}
+
+/// 波括弧の対応で範囲を求める（文字列中の括弧は区別しない）
+fn find_braced(language: Language, content: &str) -> Vec<FunctionSpan> {
+    /// 範囲の終わりを探している定義
+    struct Open {
+        span: FunctionSpan,
+        /// 定義の行の時点の括弧の深さ
+        depth: usize,
+        /// 本体の `{` が現れたか（C言語のように次の行で開く場合がある）
+        opened: bool,
+    }
+
+    let mut spans = Vec::new();
+    let mut stack: Vec<Open> = Vec::new();
+    let mut depth = 0usize;
+    let mut comments = CommentTracker::default();
+
+    for (index, line) in content.lines().enumerate() {
+        let code = line.trim();
+        if comments.is_comment(language, code) {
+            continue;
+        }
+        let code = code.split_once("//").map_or(code, |(code, _)| code).trim_end();
+
+        if let Some(name) = definition_name(language, code) {
+            // 本体が現れないまま次の定義が来たものは宣言とみなして捨てる
+            if stack.last().is_some_and(|open| !open.opened) {
+                stack.pop();
+            }
+            stack.push(Open {
+                span: FunctionSpan {
+                    name,
+                    start: index,
+                    end: index,
+                },
+                depth,
+                opened: false,
+            });
+        }
+
+        for c in code.chars() {
+            match c {
+                '{' => {
+                    if let Some(open) = stack.last_mut().filter(|open| !open.opened && open.depth == depth) {
+                        open.opened = true;
+                    }
+                    depth += 1;
+                }
+                '}' => {
+                    depth = depth.saturating_sub(1);
+                    while let Some(open) = stack.pop_if(|open| open.opened && depth <= open.depth) {
+                        spans.push(FunctionSpan { end: index, ..open.span });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // 本体のない宣言（`fn f();` やプロトタイプ宣言）
+        if code.ends_with(';') {
+            stack.pop_if(|open| !open.opened);
+        }
+    }
+
+    // 閉じていない定義はファイルの終わりまで
+    let last = content.lines().count().saturating_sub(1);
+    spans.extend(
+        stack
+            .into_iter()
+            .filter(|open| open.opened)
+            .map(|open| FunctionSpan { end: last, ..open.span }),
+    );
+    // 閉じた順に並んでいるので、外側の定義が先になるよう開始行で並べ直す
+    spans.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| b.end.cmp(&a.end)));
+    spans
+}
+
+/// インデントで範囲を求める（Python）
+fn find_indented(content: &str) -> Vec<FunctionSpan> {
+    let mut spans: Vec<FunctionSpan> = Vec::new();
+    // 範囲の終わりを探している定義（スパンの位置と定義の行のインデント）
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    let mut last_code = 0;
+
+    for (index, line) in content.lines().enumerate() {
+        let code = line.trim();
+        if code.is_empty() || code.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        while stack.last().is_some_and(|&(_, open_indent)| indent <= open_indent) {
+            if let Some((span, _)) = stack.pop() {
+                spans[span].end = last_code;
+            }
+        }
+
+        let code = code.strip_prefix("async ").unwrap_or(code);
+        let name = ["def ", "class "].iter().find_map(|keyword| code.strip_prefix(keyword)).map(|rest| {
+            rest.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect::<String>()
+        });
+        if let Some(name) = name.filter(|name| !name.is_empty()) {
+            spans.push(FunctionSpan {
+                name,
+                start: index,
+                end: index,
+            });
+            stack.push((spans.len() - 1, indent));
+        }
+        last_code = index;
+    }
+
+    for (span, _) in stack {
+        spans[span].end = last_code;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::test_repo::TestRepo;
+    use crate::analyzer::GitAnalyzer;
+    use std::path::PathBuf;
+
+    fn spans(file_name: &str, content: &str) -> Vec<(String, usize, usize)> {
+        find_functions(Path::new(file_name), content)
+            .into_iter()
+            .map(|span| (span.name, span.start, span.end))
+            .collect()
+    }
+
+    #[test]
+    fn finds_rust_functions_inside_impl_blocks() {
+        let content = "\
+use std::fmt;
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, \"{}\", self.name)
+    }
+}
+
+// fn commented_out() {}
+fn helper(callback: fn(u8)) {
+    callback(1);
+}
+
+fn declared();
+";
+        assert_eq!(
+            spans("lib.rs", content),
+            vec![
+                ("impl fmt::Display for Node".to_string(), 2, 6),
+                ("fmt".to_string(), 3, 5),
+                ("helper".to_string(), 9, 11),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_c_functions_with_the_brace_on_the_next_line() {
+        let content = "\
+int add(int a, int b);
+
+/*
+ * int commented(void) {
+ */
+int add(int a, int b)
+{
+    return a + b;
+}
+";
+        assert_eq!(spans("math.c", content), vec![("add".to_string(), 5, 8)]);
+    }
+
+    #[test]
+    fn finds_go_methods_and_script_arrow_functions() {
+        let go = "func (s *Server) Serve() error {\n\treturn nil\n}\n";
+        assert_eq!(spans("server.go", go), vec![("Serve".to_string(), 0, 2)]);
+
+        let script = "const render = (props) => {\n  return props.name;\n};\nfunction main() {\n}\n";
+        assert_eq!(
+            spans("app.js", script),
+            vec![("render".to_string(), 0, 2), ("main".to_string(), 3, 4)]
+        );
+    }
+
+    #[test]
+    fn finds_python_functions_by_indentation() {
+        let content = "\
+class Parser:
+    def parse(self, text):
+        # comment
+        return text
+
+    async def close(self):
+        pass
+
+def main():
+    Parser().parse('')
+";
+        assert_eq!(
+            spans("parser.py", content),
+            vec![
+                ("Parser".to_string(), 0, 6),
+                ("parse".to_string(), 1, 3),
+                ("close".to_string(), 5, 6),
+                ("main".to_string(), 8, 9),
+            ]
+        );
+    }
+
+    fn record(repo: &TestRepo, id: Oid) -> CommitRecord {
+        CommitRecord {
+            id,
+            time: repo.repo.find_commit(id).unwrap().time().seconds(),
+            parent_count: 1,
+            files: vec![PathBuf::from("lib.rs")],
+            merge_files: Vec::new(),
+            fix: false,
+            author: "dev".to_string(),
+            email: "dev@example.com".to_string(),
+        }
+    }
+
+    fn changes(hotspots: &FunctionHotspots) -> Vec<(&str, usize)> {
+        hotspots
+            .functions
+            .iter()
+            .map(|function| (function.name.as_str(), function.changes))
+            .collect()
+    }
+
+    #[test]
+    fn maps_hunks_onto_current_functions() {
+        let mut repo = TestRepo::new("functions-map");
+        let added = repo.commit("lib.rs", "fn alpha() {\n    1;\n}\n\nfn beta() {\n    2;\n}\n");
+        let alpha = repo.commit("lib.rs", "fn alpha() {\n    10;\n}\n\nfn beta() {\n    2;\n}\n");
+        let beta = repo.commit("lib.rs", "fn alpha() {\n    10;\n}\n\nfn beta() {\n    20;\n}\n");
+        // 先頭に関数を追加して、以前の変更の行番号をずらす
+        let gamma = repo.commit(
+            "lib.rs",
+            "fn gamma() {\n    3;\n}\n\nfn alpha() {\n    10;\n}\n\nfn beta() {\n    20;\n}\n",
+        );
+        let alpha_again = repo.commit(
+            "lib.rs",
+            "fn gamma() {\n    3;\n}\n\nfn alpha() {\n    100;\n}\n\nfn beta() {\n    20;\n}\n",
+        );
+
+        let records: Vec<CommitRecord> = [added, alpha, beta, gamma, alpha_again]
+            .into_iter()
+            .map(|id| record(&repo, id))
+            .collect();
+        let hotspots = hotspots(&repo.repo, alpha_again, Path::new("lib.rs"), &records).unwrap();
+        assert_eq!(hotspots.commits, 5);
+        // 関数の間の空行は関数の外として数える
+        assert_eq!(
+            changes(&hotspots),
+            vec![("alpha", 3), (TOP_LEVEL, 2), ("beta", 2), ("gamma", 1)]
+        );
+        assert_eq!(hotspots.functions[0].line, 5);
+    }
+
+    #[test]
+    fn counts_only_the_given_commits_but_maps_through_the_others() {
+        let mut repo = TestRepo::new("functions-filter");
+        repo.commit("lib.rs", "fn alpha() {\n    1;\n}\n\nfn beta() {\n    2;\n}\n");
+        let beta = repo.commit("lib.rs", "fn alpha() {\n    1;\n}\n\nfn beta() {\n    20;\n}\n");
+        // 数えないコミット（フィルタで除いたもの）でも行番号はずれる
+        let shifted = repo.commit(
+            "lib.rs",
+            "// header\n// lines\nfn alpha() {\n    1;\n}\n\nfn beta() {\n    20;\n}\n",
+        );
+
+        let records = vec![record(&repo, beta)];
+        let hotspots = hotspots(&repo.repo, shifted, Path::new("lib.rs"), &records).unwrap();
+        assert_eq!(hotspots.commits, 1);
+        assert_eq!(changes(&hotspots), vec![("beta", 1)]);
+        assert_eq!(hotspots.functions[0].line, 7);
+    }
+
+    #[test]
+    fn skips_commits_that_do_not_touch_the_file() {
+        let mut repo = TestRepo::new("functions-untouched");
+        let added = repo.commit("lib.rs", "fn alpha() {\n    1;\n}\n");
+        let other = repo.commit("other.txt", "unrelated\n");
+
+        let records = vec![record(&repo, added)];
+        let hotspots = hotspots(&repo.repo, other, Path::new("lib.rs"), &records).unwrap();
+        assert_eq!(hotspots.commits, 1);
+        assert_eq!(changes(&hotspots), vec![("alpha", 1)]);
+    }
+
+    #[test]
+    fn counts_the_loader_records_that_touch_the_file() {
+        let mut repo = TestRepo::new("functions-records");
+        let added = repo.commit("lib.rs", "fn alpha() {\n    1;\n}\n");
+        let other = repo.commit("other.txt", "unrelated\n");
+
+        // ローダーが変更頻度を求めたコミットには他のファイルだけを変更したものも含まれる
+        let records = vec![
+            record(&repo, added),
+            CommitRecord {
+                files: vec![PathBuf::from("other.txt")],
+                ..record(&repo, other)
+            },
+        ];
+        let analyzer = GitAnalyzer::new(&repo.dir).unwrap();
+        let hotspots = analyzer.function_hotspots_at(other, Path::new("lib.rs"), &records).unwrap();
+        assert_eq!(hotspots.commits, 1);
+        assert_eq!(changes(&hotspots), vec![("alpha", 1)]);
+    }
+}
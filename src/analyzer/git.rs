@@ -1,12 +1,194 @@
+use super::blame::{self, BlameSummary};
+use super::filter::CommitFilter;
+use super::functions::{self, FunctionHotspots};
+use super::history::FixClassifier;
+use super::scanner::LineSplitter;
+use crate::data::FileStatus;
 use anyhow::Result;
-use git2::{Repository, DiffOptions};
-use std::collections::HashMap;
+use git2::{
+    DiffOptions, Mailmap, ObjectType, Oid, Repository, Signature, Sort, Status, StatusOptions, Tree,
+    TreeWalkMode, TreeWalkResult,
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// キャッシュファイルのフォーマットバージョン（構造を変えたら上げる）
+const CACHE_VERSION: u32 = 7;
+
+/// 並列に差分計算するコミット数の単位（進捗通知とキャッシュ保存の間隔）
+const CHUNK_SIZE: usize = 256;
+
+/// 解析中にキャッシュを保存する間隔（保存のたびにファイル全体を書き直すため、チャンクごとには保存しない）
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// キャッシュの保存（保存済みのキャッシュとの合わせ込みと置き換え）をスレッド間で直列にする
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
 
 pub struct GitAnalyzer {
     repo: Repository,
 }
 
+/// 1コミット分の解析結果（キャッシュの単位）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitRecord {
+    /// コミットID（キャッシュではキーとして持つため保存しない）
+    #[serde(skip, default = "Oid::zero")]
+    pub id: Oid,
+    /// コミット日時（UNIX秒）
+    pub time: i64,
+    /// 親コミットの数（2以上ならマージコミット）
+    pub parent_count: usize,
+    /// 最初の親との差分で変更されたファイル（最初のコミットは追加したすべてのファイル）
+    pub files: Vec<PathBuf>,
+    /// マージコミットの場合、すべての親と異なるファイル
+    pub merge_files: Vec<PathBuf>,
+    /// 修正コミットか（コミットメッセージを修正コミットの判定条件で判定した結果）
+    pub fix: bool,
+    /// 作者の名前とメールアドレス（走査時にmailmapで名寄せする）
+    pub author: String,
+    pub email: String,
+}
+
+impl CommitRecord {
+    /// 親のない最初のコミットか（`files` は変更ではなく追加したすべてのファイル）
+    pub fn is_root(&self) -> bool {
+        self.parent_count == 0
+    }
+}
+
+/// 履歴の走査を始める参照
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RefSpec {
+    /// 現在のHEAD
+    #[default]
+    Head,
+    /// 単一のリビジョン（ブランチ名、タグ、コミットIDなど）
+    Rev(String),
+    /// `base..tip` 形式の範囲（tipから到達でき、baseからは到達できないコミット）
+    Range(String),
+    /// すべてのローカルブランチ
+    AllBranches,
+    /// すべての参照（ブランチ、タグ、リモート追跡ブランチなど）
+    AllRefs,
+}
+
+impl RefSpec {
+    /// 画面に表示する走査対象
+    pub fn label(&self) -> String {
+        match self {
+            Self::Head => "HEAD".to_string(),
+            Self::Rev(rev) | Self::Range(rev) => rev.clone(),
+            Self::AllBranches => "all branches".to_string(),
+            Self::AllRefs => "all refs".to_string(),
+        }
+    }
+}
+
+/// 履歴の走査条件
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    /// 走査を始める参照
+    pub refs: RefSpec,
+    /// 走査するコミット数の上限
+    pub max_commits: Option<usize>,
+    /// この日時（UNIX秒）より古いコミットに達したら走査を打ち切る
+    pub since: Option<i64>,
+    /// マージコミットの扱い
+    pub merges: MergePolicy,
+    /// 数えないコミットの条件
+    pub filter: CommitFilter,
+    /// 修正コミットの判定条件
+    pub fixes: FixClassifier,
+}
+
+/// マージコミットの数え方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// 最初の親との差分で数える（マージしたブランチ側の変更も含まれる）
+    #[default]
+    ParentDiff,
+    /// マージコミットを数えない（マージコミットを作るチーム向け）
+    Skip,
+    /// 最初の親だけをたどり、マージしたブランチ側のコミットは走査しない（squashマージ相当）
+    FirstParent,
+    /// すべての親と異なるファイルだけを数える（コンフリクト解消などマージ自体での変更）
+    AllParents,
+}
+
+impl MergePolicy {
+    /// コマンドライン引数の値から変換
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "parent" => Some(Self::ParentDiff),
+            "skip" => Some(Self::Skip),
+            "first-parent" => Some(Self::FirstParent),
+            "all-parents" => Some(Self::AllParents),
+            _ => None,
+        }
+    }
+
+    /// ポリシーに従ってレコードを変換（数えない場合はNone）
+    fn apply(self, record: &CommitRecord) -> Option<CommitRecord> {
+        if record.parent_count <= 1 {
+            return Some(record.clone());
+        }
+
+        match self {
+            Self::ParentDiff | Self::FirstParent => Some(record.clone()),
+            Self::Skip => None,
+            Self::AllParents => Some(CommitRecord {
+                files: record.merge_files.clone(),
+                ..record.clone()
+            }),
+        }
+    }
+}
+
+impl HistoryQuery {
+    /// HEADから直近 `days` 日分を想定したコミット数を走査する
+    pub fn recent(days: usize) -> Self {
+        Self {
+            max_commits: Some(days * 10), // 1日10コミット程度を想定
+            ..Self::default()
+        }
+    }
+
+    /// コミット数と期間の制限を外し、全履歴を走査する（その他の条件は引き継ぐ）
+    pub fn full(&self) -> Self {
+        Self {
+            max_commits: None,
+            since: None,
+            ..self.clone()
+        }
+    }
+
+    /// 指定したコミットから `since` 以降のコミットを走査する（その他の条件は引き継ぐ）
+    pub fn window(&self, start: Oid, since: i64) -> Self {
+        Self {
+            refs: RefSpec::Rev(start.to_string()),
+            max_commits: None,
+            since: Some(since),
+            ..self.clone()
+        }
+    }
+}
+
+/// `.git/heatree/` に保存するコミットID単位のキャッシュ
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CommitCache {
+    version: u32,
+    /// `fix` を判定した条件（設定ファイルで変えた場合は判定だけをやり直す）
+    fix_patterns: Vec<String>,
+    commits: HashMap<String, CommitRecord>,
+}
+
 impl GitAnalyzer {
     /// リポジトリを開く
     pub fn new(path: &Path) -> Result<Self> {
@@ -14,65 +196,435 @@ impl GitAnalyzer {
         Ok(Self { repo })
     }
 
-    /// 各ファイルの変更頻度を計算
-    pub fn analyze_change_frequency(&self, days: usize) -> Result<HashMap<PathBuf, f64>> {
-        let mut changes: HashMap<PathBuf, usize> = HashMap::new();
-
+    /// 走査条件に一致するコミットを新しい順に解析する
+    /// キャッシュ済みのコミットは再計算せず、未解析のものだけをチャンク単位で並列に差分計算する。
+    /// チャンクごとに `on_chunk(解析済みレコード, 処理済み件数, 全件数)` が呼ばれる
+    pub fn collect_commits<F>(&self, query: &HistoryQuery, mut on_chunk: F) -> Result<Vec<CommitRecord>>
+    where
+        F: FnMut(&[CommitRecord], usize, usize),
+    {
+        // 複数の参照から到達できるコミットもrevwalkが1度だけ返す
         let mut revwalk = self.repo.revwalk()?;
-        revwalk.push_head()?;
-
-        // 最新のコミットから指定日数分を取得
-        let mut commit_count = 0;
-        let max_commits = days * 10; // 1日10コミット程度を想定
+        revwalk.set_sorting(Sort::TIME)?;
+        if query.merges == MergePolicy::FirstParent {
+            revwalk.simplify_first_parent()?;
+        }
+        match &query.refs {
+            RefSpec::Head => revwalk.push_head()?,
+            RefSpec::Rev(rev) => revwalk.push(self.resolve_commit(rev)?)?,
+            RefSpec::Range(range) => revwalk.push_range(range)?,
+            RefSpec::AllBranches => revwalk.push_glob("heads")?,
+            // コミットを指さない参照（ツリーへのタグなど）は無視される
+            RefSpec::AllRefs => revwalk.push_glob("*")?,
+        }
 
+        let mut oids = Vec::new();
         for oid in revwalk {
-            if commit_count >= max_commits {
+            if query.max_commits.is_some_and(|max| oids.len() >= max) {
                 break;
             }
 
             let oid = oid?;
-            let commit = self.repo.find_commit(oid)?;
+            // 日時順に走査しているので、期間外のコミットに達したら以降はすべて期間外
+            if let Some(since) = query.since {
+                if self.repo.find_commit(oid)?.time().seconds() < since {
+                    break;
+                }
+            }
+            oids.push(oid);
+        }
+
+        let mut cache = self.load_cache();
+        let mut unsaved = self.reclassify_fixes(&mut cache, &query.fixes);
+        // .mailmapの変更がキャッシュに影響しないよう、名寄せはレコードを取り出すたびに行う
+        let mailmap = self.repo.mailmap().ok();
+        let missing: Vec<Oid> = oids
+            .iter()
+            .filter(|oid| !cache.commits.contains_key(&oid.to_string()))
+            .copied()
+            .collect();
+
+        let total = oids.len();
+        let mut done = total - missing.len();
+        on_chunk(&resolve_records(&self.repo, &oids, &cache, query, mailmap.as_ref()), done, total);
 
-            if commit.parent_count() == 0 {
+        // Repositoryはスレッド間で共有できないため、ワーカーごとに開き直す
+        let git_dir = self.repo.path().to_path_buf();
+        let mut last_save = Instant::now();
+        for chunk in missing.chunks(CHUNK_SIZE) {
+            let computed = chunk
+                .par_iter()
+                .map_init(
+                    || Repository::open(&git_dir),
+                    |repo, oid| {
+                        let repo = repo.as_ref().map_err(|e| anyhow::anyhow!("{}", e))?;
+                        Ok((oid.to_string(), diff_commit(repo, *oid, &query.fixes)?))
+                    },
+                )
+                .collect::<Result<Vec<_>>>()?;
+
+            cache.commits.extend(computed);
+            unsaved = true;
+            // 途中で終了しても次回に引き継げるよう一定間隔で保存（失敗しても解析結果は使える）
+            if last_save.elapsed() >= SAVE_INTERVAL {
+                let _ = self.save_cache(&mut cache, &query.fixes);
+                last_save = Instant::now();
+                unsaved = false;
+            }
+
+            done += chunk.len();
+            on_chunk(&resolve_records(&self.repo, &oids, &cache, query, mailmap.as_ref()), done, total);
+        }
+        if unsaved {
+            let _ = self.save_cache(&mut cache, &query.fixes);
+        }
+
+        Ok(resolve_records(&self.repo, &oids, &cache, query, mailmap.as_ref()))
+    }
+
+    /// 作業ツリーの変更状態（git status）を取得
+    pub fn working_tree_status(&self) -> Result<HashMap<PathBuf, FileStatus>> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+
+        let statuses = self.repo.statuses(Some(&mut opts))?;
+        let mut result = HashMap::new();
+
+        for entry in statuses.iter() {
+            let Some(path) = entry.path() else {
                 continue;
+            };
+            if let Some(status) = classify_status(entry.status()) {
+                result.insert(PathBuf::from(path), status);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// リビジョン指定（ブランチ名、タグ、コミットIDなど）をコミットIDに解決
+    pub fn resolve_commit(&self, rev: &str) -> Result<Oid> {
+        Ok(self.repo.revparse_single(rev)?.peel_to_commit()?.id())
+    }
+
+    /// コミット日時（UNIX秒）
+    pub fn commit_time(&self, oid: Oid) -> Result<i64> {
+        Ok(self.repo.find_commit(oid)?.time().seconds())
+    }
+
+    /// HEADの履歴のうち、指定日時以前で最新のコミット
+    pub fn last_commit_before(&self, time: i64) -> Result<Option<Oid>> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::TIME)?;
+        revwalk.push_head()?;
+
+        for oid in revwalk {
+            let oid = oid?;
+            if self.commit_time(oid)? <= time {
+                return Ok(Some(oid));
             }
+        }
+        Ok(None)
+    }
+
+    /// 指定コミット時点のファイルをblameし、作者ごとの行数と行の年齢の分布を求める
+    pub fn blame_at(&self, commit: Oid, relative_path: &Path) -> Result<BlameSummary> {
+        blame::summarize(&self.repo, commit, relative_path)
+    }
+
+    /// 指定コミット時点のファイルの関数ごとの変更回数を求める
+    /// 数えるのは `records`（ローダーが変更頻度を求めたコミット）のうちファイルを変更したもの
+    pub fn function_hotspots_at(
+        &self,
+        commit: Oid,
+        relative_path: &Path,
+        records: &[CommitRecord],
+    ) -> Result<FunctionHotspots> {
+        let records: Vec<CommitRecord> = records
+            .iter()
+            .filter(|record| record.files.iter().any(|file| file == relative_path))
+            .cloned()
+            .collect();
+        functions::hotspots(&self.repo, commit, relative_path, &records)
+    }
 
-            let parent = commit.parent(0)?;
-            let parent_tree = parent.tree()?;
-            let commit_tree = commit.tree()?;
-
-            let mut diff_opts = DiffOptions::new();
-            let diff = self.repo.diff_tree_to_tree(
-                Some(&parent_tree),
-                Some(&commit_tree),
-                Some(&mut diff_opts),
-            )?;
-
-            diff.foreach(
-                &mut |delta, _| {
-                    if let Some(path) = delta.new_file().path() {
-                        let path = path.to_path_buf();
-                        *changes.entry(path).or_insert(0) += 1;
+    /// 指定コミットのツリーに含まれる各ファイルの行数をblobから数える
+    pub fn line_counts_at(&self, oid: Oid) -> Result<HashMap<PathBuf, usize>> {
+        let tree = self.repo.find_commit(oid)?.tree()?;
+        let mut counts = HashMap::new();
+
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            let name = String::from_utf8_lossy(entry.name_bytes()).to_string();
+            // スキャン時と同様に隠しファイル/ディレクトリは対象外
+            if name.starts_with('.') {
+                return TreeWalkResult::Skip;
+            }
+
+            if entry.kind() == Some(ObjectType::Blob) {
+                let lines = match self.repo.find_blob(entry.id()) {
+                    Ok(blob) if !blob.is_binary() => {
+                        let mut splitter = LineSplitter::default();
+                        splitter.feed(blob.content(), |_| {});
+                        splitter.finish(|_| {})
                     }
-                    true
-                },
-                None,
-                None,
-                None,
-            )?;
-
-            commit_count += 1;
-        }
-
-        // 変更頻度を日次に正規化
-        let frequency: HashMap<PathBuf, f64> = changes
-            .into_iter()
-            .map(|(path, count)| {
-                let freq = count as f64 / days as f64;
-                (path, freq)
+                    _ => 0,
+                };
+                counts.insert(Path::new(dir).join(name), lines);
+            }
+            TreeWalkResult::Ok
+        })?;
+
+        Ok(counts)
+    }
+
+    fn cache_path(&self) -> PathBuf {
+        self.repo.path().join("heatree").join("commits.json")
+    }
+
+    fn load_cache(&self) -> CommitCache {
+        fs::read(self.cache_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CommitCache>(&bytes).ok())
+            .filter(|cache| cache.version == CACHE_VERSION)
+            .unwrap_or_else(|| CommitCache {
+                version: CACHE_VERSION,
+                ..CommitCache::default()
             })
-            .collect();
+    }
+
+    /// 修正コミットの判定条件がキャッシュ時と異なれば、キャッシュ済みのコミットの判定だけをやり直す
+    /// （差分は再計算せず、メッセージだけを読み直す）。やり直した場合はtrue
+    fn reclassify_fixes(&self, cache: &mut CommitCache, fixes: &FixClassifier) -> bool {
+        let patterns = fixes.sources();
+        if cache.fix_patterns == patterns {
+            return false;
+        }
+        for (oid, record) in &mut cache.commits {
+            let commit = Oid::from_str(oid).ok().and_then(|oid| self.repo.find_commit(oid).ok());
+            record.fix = commit.is_some_and(|commit| fixes.is_fix(commit.message().unwrap_or_default()));
+        }
+        cache.fix_patterns = patterns;
+        true
+    }
+
+    /// キャッシュを保存する
+    /// 読み込んだ後に他のスレッドや別のプロセスが保存したコミットを失わないよう、保存済みのキャッシュを読み直して合わせる。
+    /// 途中で終了しても壊れないよう、一時ファイルに書いてから置き換える
+    fn save_cache(&self, cache: &mut CommitCache, fixes: &FixClassifier) -> Result<()> {
+        let path = self.cache_path();
+        let Some(dir) = path.parent() else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir)?;
+
+        // 同じプロセス内では読み直してから置き換えるまでを直列にする
+        let _guard = SAVE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut saved = self.load_cache();
+        self.reclassify_fixes(&mut saved, fixes);
+        for (oid, record) in saved.commits {
+            cache.commits.entry(oid).or_insert(record);
+        }
+
+        let temp = dir.join(format!("commits.{}.tmp", process::id()));
+        let result = (|| -> Result<()> {
+            let mut writer = BufWriter::new(File::create(&temp)?);
+            serde_json::to_writer(&mut writer, cache)?;
+            writer.flush()?;
+            fs::rename(&temp, &path)?;
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        result
+    }
+}
+
+/// git2のステータスフラグを表示用の状態に変換
+/// 複数の状態を持つ場合は 削除 > 未ステージの変更 > ステージ済み > 未追跡 の順で優先する
+fn classify_status(status: Status) -> Option<FileStatus> {
+    if status.contains(Status::WT_DELETED)
+        || (status.contains(Status::INDEX_DELETED) && !status.contains(Status::WT_NEW))
+    {
+        Some(FileStatus::Deleted)
+    } else if status.intersects(Status::WT_MODIFIED | Status::WT_RENAMED | Status::WT_TYPECHANGE) {
+        Some(FileStatus::Modified)
+    } else if status.intersects(
+        Status::INDEX_NEW | Status::INDEX_MODIFIED | Status::INDEX_RENAMED | Status::INDEX_TYPECHANGE,
+    ) {
+        Some(FileStatus::Staged)
+    } else if status.contains(Status::WT_NEW) {
+        Some(FileStatus::Untracked)
+    } else {
+        None
+    }
+}
+
+/// 走査順にキャッシュからレコードを取り出す（未解析のコミットは飛ばす）
+/// マージコミットは走査条件のポリシーに従って除外・置き換えし、作者をmailmapで名寄せしてからフィルタを適用する
+/// メッセージはキャッシュに持たないため、メッセージで絞り込む場合だけリポジトリから読む
+fn resolve_records(
+    repo: &Repository,
+    oids: &[Oid],
+    cache: &CommitCache,
+    query: &HistoryQuery,
+    mailmap: Option<&Mailmap>,
+) -> Vec<CommitRecord> {
+    oids.iter()
+        .filter_map(|&commit| {
+            let oid = commit.to_string();
+            let mut record = query.merges.apply(cache.commits.get(&oid)?)?;
+            record.id = commit;
+            if let Some(mailmap) = mailmap {
+                let signature = Signature::now(&record.author, &record.email)
+                    .and_then(|signature| mailmap.resolve_signature(&signature));
+                if let Ok(signature) = signature {
+                    record.author = signature.name().unwrap_or_default().to_string();
+                    record.email = signature.email().unwrap_or_default().to_string();
+                }
+            }
+            let message = if query.filter.filters_messages() {
+                repo.find_commit(commit)
+                    .map(|commit| commit.message().unwrap_or_default().to_string())
+                    .unwrap_or_default()
+            } else {
+                String::new()
+            };
+            query.filter.apply(&oid, record, &message)
+        })
+        .collect()
+}
+
+/// 1コミットを親と比較し、変更されたファイルを取得
+/// マージコミットはすべての親と異なるファイルも合わせて記録する
+fn diff_commit(repo: &Repository, oid: Oid, fixes: &FixClassifier) -> Result<CommitRecord> {
+    let commit = repo.find_commit(oid)?;
+    let time = commit.time().seconds();
+    let parent_count = commit.parent_count();
+    let fix = fixes.is_fix(commit.message().unwrap_or_default());
+    let author = commit.author().name().unwrap_or_default().to_string();
+    let email = commit.author().email().unwrap_or_default().to_string();
+    let commit_tree = commit.tree()?;
+
+    // 最初のコミットは空のツリーと比較する
+    let parent_tree = match parent_count {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    let files = diff_paths(repo, parent_tree.as_ref(), &commit_tree)?;
+
+    let mut merge_files = Vec::new();
+    if parent_count > 1 {
+        // いずれかの親と同じ内容のファイルはマージで取り込んだだけなので除外する
+        let mut common: HashSet<PathBuf> = files.iter().cloned().collect();
+        for parent in commit.parents().skip(1) {
+            let changed: HashSet<PathBuf> = diff_paths(repo, Some(&parent.tree()?), &commit_tree)?
+                .into_iter()
+                .collect();
+            common.retain(|path| changed.contains(path));
+        }
+        merge_files = files.iter().filter(|path| common.contains(*path)).cloned().collect();
+    }
+
+    Ok(CommitRecord {
+        id: oid,
+        time,
+        parent_count,
+        files,
+        merge_files,
+        fix,
+        author,
+        email,
+    })
+}
+
+/// 2つのツリー間で変更されたファイル（`old_tree` がNoneなら新しいツリーのすべてのファイル）
+fn diff_paths(repo: &Repository, old_tree: Option<&Tree>, new_tree: &Tree) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    let mut diff_opts = DiffOptions::new();
+    let diff = repo.diff_tree_to_tree(old_tree, Some(new_tree), Some(&mut diff_opts))?;
+
+    diff.foreach(
+        &mut |delta, _| {
+            if let Some(path) = delta.new_file().path() {
+                files.push(path.to_path_buf());
+            }
+            true
+        },
+        None,
+        None,
+        None,
+    )?;
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::history::{file_ages, ActivityBucket, FrequencyMode, HistorySpec};
+    use crate::analyzer::test_repo::TestRepo;
+
+    /// `oids` だけを解析したキャッシュ
+    fn cache_of(repo: &TestRepo, oids: &[Oid]) -> CommitCache {
+        CommitCache {
+            version: CACHE_VERSION,
+            fix_patterns: Vec::new(),
+            commits: oids
+                .iter()
+                .map(|&oid| (oid.to_string(), diff_commit(&repo.repo, oid, &FixClassifier::default()).unwrap()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn keeps_commits_saved_by_others_since_loading() {
+        let mut repo = TestRepo::new("git-cache-merge");
+        let first = repo.commit("a.rs", "a\n");
+        let second = repo.commit("b.rs", "b\n");
+        let analyzer = GitAnalyzer::new(&repo.dir).unwrap();
+        let fixes = FixClassifier::default();
+
+        // 同時に読み込んだ2つのキャッシュがそれぞれ別のコミットを解析して保存する
+        let mut early = cache_of(&repo, &[first]);
+        let mut late = cache_of(&repo, &[second]);
+        analyzer.save_cache(&mut early, &fixes).unwrap();
+        analyzer.save_cache(&mut late, &fixes).unwrap();
+
+        let saved = analyzer.load_cache();
+        assert!(saved.commits.contains_key(&first.to_string()));
+        assert!(saved.commits.contains_key(&second.to_string()));
+        // 保存したキャッシュにも他が保存したコミットが入る
+        assert_eq!(late.commits.len(), 2);
+    }
+
+    #[test]
+    fn records_the_files_added_by_the_root_commit() {
+        let mut repo = TestRepo::new("git-root-files");
+        let root = repo.commit_files(&[("old.rs", "old\n"), ("lib.rs", "lib\n")]);
+        let change = repo.commit("lib.rs", "lib changed\n");
+        let analyzer = GitAnalyzer::new(&repo.dir).unwrap();
+        let records = analyzer.collect_commits(&HistoryQuery::default(), |_, _, _| {}).unwrap();
+        let time = |oid: Oid| repo.repo.find_commit(oid).unwrap().time().seconds();
+
+        // 最初のコミットにしかないファイルにも年齢があり、後で変更したファイルは追加した日時から数える
+        let ages = file_ages(&records);
+        let old = ages.get(Path::new("old.rs")).unwrap();
+        assert_eq!((old.first_seen, old.last_changed), (time(root), time(root)));
+        let lib = ages.get(Path::new("lib.rs")).unwrap();
+        assert_eq!((lib.first_seen, lib.last_changed), (time(root), time(change)));
 
-        Ok(frequency)
+        // 変更頻度とコミット数には最初のコミットを数えない
+        let spec = HistorySpec {
+            days: 30,
+            mode: FrequencyMode::Flat,
+            bucket: ActivityBucket::Week,
+        };
+        let metrics = spec.compute(&records, time(change));
+        assert_eq!(metrics.commits.get(Path::new("lib.rs")), Some(&1));
+        assert_eq!(metrics.commits.get(Path::new("old.rs")), None);
     }
 }
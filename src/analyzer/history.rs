@@ -0,0 +1,260 @@
+use super::git::CommitRecord;
+use crate::data::CodeAge;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::f64::consts::LN_2;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 1日の秒数
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// 現在日時（UNIX秒）
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// 変更回数の重み付け
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FrequencyMode {
+    /// 期間内の変更をすべて同じ重みで数える
+    #[default]
+    Flat,
+    /// 新しい変更ほど重く数える指数減衰（`half_life` 日経つと重みが半分になる）
+    Decay { half_life: f64 },
+}
+
+/// アクティビティ（スパークライン）を集計する単位
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ActivityBucket {
+    Day,
+    #[default]
+    Week,
+}
+
+impl ActivityBucket {
+    /// コマンドライン引数の値から変換
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "day" => Some(Self::Day),
+            "week" => Some(Self::Week),
+            _ => None,
+        }
+    }
+
+    fn seconds(self) -> i64 {
+        match self {
+            Self::Day => SECONDS_PER_DAY,
+            Self::Week => 7 * SECONDS_PER_DAY,
+        }
+    }
+}
+
+/// コミットメッセージから修正コミットを判定する
+#[derive(Debug, Clone, Default)]
+pub struct FixClassifier {
+    patterns: Vec<Regex>,
+}
+
+impl FixClassifier {
+    /// 正規表現の一覧から作成（いずれかに一致するメッセージを修正コミットとみなす）
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern).with_context(|| format!("invalid fix pattern: {}", pattern)))
+            .collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    pub fn is_fix(&self, message: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(message))
+    }
+
+    /// 判定に使う正規表現（キャッシュした判定結果が同じ条件によるものかを確かめる）
+    pub fn sources(&self) -> Vec<String> {
+        self.patterns.iter().map(|pattern| pattern.as_str().to_string()).collect()
+    }
+}
+
+/// 履歴から求めるメトリクスの計算条件
+#[derive(Debug, Clone)]
+pub struct HistorySpec {
+    /// 変更頻度を計算する期間（日数）
+    pub days: usize,
+    pub mode: FrequencyMode,
+    pub bucket: ActivityBucket,
+}
+
+/// 履歴から求めたファイルごとのメトリクス（キーはリポジトリルートからの相対パス）
+#[derive(Debug, Clone, Default)]
+pub struct HistoryMetrics {
+    /// 変更頻度（日次）
+    pub frequency: HashMap<PathBuf, f64>,
+    /// 期間内のコミット数をバケットごとに数えたもの（古い順）
+    pub activity: HashMap<PathBuf, Vec<u32>>,
+    /// 解析したコミットのうちファイルを変更したものの数
+    pub commits: HashMap<PathBuf, usize>,
+    /// そのうち修正コミットの数
+    pub fixes: HashMap<PathBuf, usize>,
+    /// 解析したコミット数
+    pub total_commits: usize,
+    /// 作者（mailmapで名寄せした名前）ごとのメトリクス（作者で絞り込んだ表示に使う）
+    pub authors: HashMap<String, HistoryMetrics>,
+    /// 作者のメールアドレス（CODEOWNERSの所有者との突き合わせに使う）
+    pub emails: HashMap<String, String>,
+}
+
+impl HistoryMetrics {
+    /// 別のコミット群から求めたメトリクスを足し合わせる（作者ごとのメトリクスとメールアドレスは対象外）
+    pub fn merge(&mut self, other: &HistoryMetrics) {
+        for (path, frequency) in &other.frequency {
+            *self.frequency.entry(path.clone()).or_insert(0.0) += frequency;
+        }
+        for (path, counts) in &other.activity {
+            let total = self.activity.entry(path.clone()).or_default();
+            if total.len() < counts.len() {
+                total.resize(counts.len(), 0);
+            }
+            for (total, count) in total.iter_mut().zip(counts) {
+                *total += count;
+            }
+        }
+        for (path, commits) in &other.commits {
+            *self.commits.entry(path.clone()).or_insert(0) += commits;
+        }
+        for (path, fixes) in &other.fixes {
+            *self.fixes.entry(path.clone()).or_insert(0) += fixes;
+        }
+        self.total_commits += other.total_commits;
+    }
+}
+
+impl HistorySpec {
+    /// コミットの解析結果から各ファイルのメトリクスを計算（作者ごとのメトリクスも合わせて求める）
+    /// `until` は期間の終わり（減衰とバケットの基準）となる日時（UNIX秒）
+    pub fn compute(&self, records: &[CommitRecord], until: i64) -> HistoryMetrics {
+        let mut by_author: HashMap<&str, Vec<&CommitRecord>> = HashMap::new();
+        for record in records {
+            by_author.entry(&record.author).or_default().push(record);
+        }
+
+        HistoryMetrics {
+            authors: by_author
+                .into_iter()
+                .map(|(author, records)| (author.to_string(), self.compute_records(records, until)))
+                .collect(),
+            emails: records
+                .iter()
+                .map(|record| (record.author.clone(), record.email.clone()))
+                .collect(),
+            ..self.compute_records(records.iter().collect(), until)
+        }
+    }
+
+    fn compute_records(&self, records: Vec<&CommitRecord>, until: i64) -> HistoryMetrics {
+        let mut changes: HashMap<PathBuf, f64> = HashMap::new();
+        let mut activity: HashMap<PathBuf, Vec<u32>> = HashMap::new();
+        let mut commits: HashMap<PathBuf, usize> = HashMap::new();
+        let mut fixes: HashMap<PathBuf, usize> = HashMap::new();
+        let bucket_count = self.bucket_count();
+        let total_commits = records.len();
+
+        for record in records {
+            // 最初のコミットが追加したファイルは年齢にだけ使い、変更としては数えない
+            if record.is_root() {
+                continue;
+            }
+            let age_seconds = until - record.time;
+            let weight = self.weight(age_seconds);
+            // 期間外（コミット数の上限で走査した古いコミットなど）はアクティビティに含めない
+            let bucket = usize::try_from(age_seconds.max(0) / self.bucket.seconds())
+                .ok()
+                .filter(|&age| age < bucket_count)
+                .map(|age| bucket_count - 1 - age);
+
+            for path in &record.files {
+                *changes.entry(path.clone()).or_insert(0.0) += weight;
+                *commits.entry(path.clone()).or_insert(0) += 1;
+                if record.fix {
+                    *fixes.entry(path.clone()).or_insert(0) += 1;
+                }
+                if let Some(bucket) = bucket {
+                    activity
+                        .entry(path.clone())
+                        .or_insert_with(|| vec![0; bucket_count])[bucket] += 1;
+                }
+            }
+        }
+
+        // 変更頻度を日次に正規化
+        let effective_days = self.effective_days();
+        let frequency = changes
+            .into_iter()
+            .map(|(path, weight)| (path, weight / effective_days))
+            .collect();
+
+        HistoryMetrics {
+            frequency,
+            activity,
+            commits,
+            fixes,
+            total_commits,
+            authors: HashMap::new(),
+            emails: HashMap::new(),
+        }
+    }
+
+    /// 期間を覆うのに必要なバケット数
+    pub fn bucket_count(&self) -> usize {
+        let seconds = self.days as i64 * SECONDS_PER_DAY;
+        ((seconds + self.bucket.seconds() - 1) / self.bucket.seconds()).max(1) as usize
+    }
+
+    fn weight(&self, age_seconds: i64) -> f64 {
+        match self.mode {
+            FrequencyMode::Flat => 1.0,
+            FrequencyMode::Decay { half_life } => {
+                let age_days = age_seconds.max(0) as f64 / SECONDS_PER_DAY as f64;
+                0.5f64.powf(age_days / half_life)
+            }
+        }
+    }
+
+    /// 期間内の重みの合計（毎日1回変更されるファイルの頻度がどちらの方法でも1.0になる）
+    fn effective_days(&self) -> f64 {
+        let days = self.days as f64;
+        match self.mode {
+            FrequencyMode::Flat => days,
+            FrequencyMode::Decay { half_life } => {
+                half_life / LN_2 * (1.0 - 0.5f64.powf(days / half_life))
+            }
+        }
+    }
+}
+
+/// コミットの解析結果から各ファイルの最初と最後の変更日時を求める（最初のコミットで追加したファイルはその日時から）
+pub fn file_ages(records: &[CommitRecord]) -> HashMap<PathBuf, CodeAge> {
+    let mut ages: HashMap<PathBuf, CodeAge> = HashMap::new();
+
+    for record in records {
+        for path in &record.files {
+            ages.entry(path.clone())
+                .and_modify(|age| {
+                    age.last_changed = age.last_changed.max(record.time);
+                    age.first_seen = age.first_seen.min(record.time);
+                    age.median_changed = age.last_changed;
+                })
+                .or_insert(CodeAge {
+                    last_changed: record.time,
+                    first_seen: record.time,
+                    median_changed: record.time,
+                });
+        }
+    }
+
+    ages
+}
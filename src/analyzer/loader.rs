@@ -0,0 +1,236 @@
+use super::blame::BlameSummary;
+use super::compare::build_compare_tree;
+use super::detail::{spawn_detail, DetailRequest};
+use super::filter::CommitFilter;
+use super::functions::FunctionHotspots;
+use super::git::{CommitRecord, HistoryQuery, RefSpec};
+use super::history::{file_ages, unix_now, FixClassifier, FrequencyMode, HistoryMetrics, HistorySpec};
+use super::markers::Markers;
+use super::travel::spawn_travel;
+use super::watcher::spawn_watcher;
+use super::scanner::build_tree_from_lines;
+use super::{scan_directory, GitAnalyzer, ScanProgress};
+use crate::cli::{Command, Options};
+use crate::data::{CodeAge, FileNode, FileStatus};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+/// バックグラウンド解析からUIへ送られるイベント
+pub enum LoadEvent {
+    /// ディレクトリスキャンが完了した
+    Tree(FileNode),
+    /// Git解析の途中経過（その時点までに解析できたコミットから求めた変更頻度とアクティビティ）
+    /// `records` はメトリクスを求めたコミットで、詳細パネルの関数ごとの変更回数でも同じコミットを数える
+    History {
+        metrics: HistoryMetrics,
+        records: Arc<Vec<CommitRecord>>,
+        done: usize,
+        total: usize,
+    },
+    /// 全履歴から求めた各ファイルの年齢（TUIでのみ、Git解析の完了後に届く）
+    Ages(HashMap<PathBuf, CodeAge>),
+    /// 期間内のGit解析が完了した（年齢はこの後に別に届く）
+    GitFinished,
+    /// 作業ツリーの変更状態（git status）
+    Status(HashMap<PathBuf, FileStatus>),
+    /// タイムトラベルで要求した期間のツリー（`offset` は何期間前か）
+    Travel { offset: usize, tree: FileNode },
+    /// 詳細パネルで要求したファイルのblame結果
+    Blame(PathBuf, Result<BlameSummary, String>),
+    /// 詳細パネルで要求したファイルの関数ごとの変更回数
+    Functions(PathBuf, Result<FunctionHotspots, String>),
+    /// 監視中のパスが変更された（削除された場合はNone）
+    PathChanged(PathBuf, Option<FileNode>),
+    /// 解析中にエラーが発生した
+    Error(String),
+}
+
+/// ディレクトリスキャンとGit解析をバックグラウンドで実行する
+pub struct Loader {
+    pub events: Receiver<LoadEvent>,
+    pub scan_progress: Arc<ScanProgress>,
+    /// タイムトラベルの要求先（作業ツリーを表示するモードでのみ使える）
+    travel: Option<Sender<usize>>,
+    /// 詳細パネルのblameと関数ごとの変更回数の要求先
+    detail: Sender<DetailRequest>,
+}
+
+impl Loader {
+    /// スキャンとGit解析をそれぞれ別スレッドで開始
+    /// `--rev` が指定された場合は作業ツリーの代わりにそのリビジョンのツリーを読む。
+    /// `--watch` が有効な場合は、初回の解析後もファイルシステムの変更を送り続ける。
+    /// UIが先に終了した場合は送信に失敗するだけで、スレッドはプロセス終了とともに破棄される
+    /// 設定ファイルの正規表現やignore-revsのファイルが不正な場合はエラーを返す
+    pub fn spawn(analyzer: GitAnalyzer, options: &Options) -> Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let scan_progress = Arc::new(ScanProgress::default());
+        let path = options.path.clone();
+        let detail = spawn_detail(path.clone(), options.rev.clone(), tx.clone());
+        let history = history_query(&analyzer, options)?;
+        let spec = HistorySpec {
+            days: options.days,
+            mode: options
+                .half_life
+                .map_or(FrequencyMode::Flat, |half_life| FrequencyMode::Decay { half_life }),
+            bucket: options.bucket,
+        };
+
+        let markers = Markers::new(&options.config.markers.names)?;
+        // 年齢は全履歴の解析が必要なため、表示するTUIでのみ求める
+        let ages = options.command == Command::Tui;
+
+        // 比較モードは作業ツリーではなく2つの時点のGitツリーから構築する
+        if let Some(compare) = options.compare.clone() {
+            thread::spawn(move || {
+                match build_compare_tree(&analyzer, &path, &compare, &history, &spec) {
+                    Ok((tree, metrics, records)) => {
+                        let _ = tx.send(LoadEvent::Tree(tree));
+                        let records = Arc::new(records);
+                        let _ = tx.send(LoadEvent::History { metrics, records, done: 0, total: 0 });
+                        let _ = tx.send(LoadEvent::GitFinished);
+                    }
+                    Err(err) => {
+                        let _ = tx.send(LoadEvent::Error(format!("compare failed: {}", err)));
+                    }
+                }
+            });
+
+            return Ok(Self {
+                events,
+                scan_progress,
+                travel: None,
+                detail,
+            });
+        }
+
+        // リビジョン指定時はチェックアウトせずにGitのツリーオブジェクトから構築する
+        if let Some(rev) = options.rev.clone() {
+            thread::spawn(move || {
+                let tree = analyzer.resolve_commit(&rev).and_then(|commit| {
+                    let lines = analyzer.line_counts_at(commit)?;
+                    Ok((build_tree_from_lines(&path, &lines), analyzer.commit_time(commit)?))
+                });
+                // 減衰の基準はそのリビジョンの日時とする
+                let until = match tree {
+                    Ok((tree, until)) => {
+                        let _ = tx.send(LoadEvent::Tree(tree));
+                        until
+                    }
+                    Err(err) => {
+                        let _ = tx.send(LoadEvent::Error(format!("failed to read {}: {}", rev, err)));
+                        return;
+                    }
+                };
+
+                // 走査対象の参照が指定されていなければ表示中のリビジョンから遡る
+                let refs = match history.refs {
+                    RefSpec::Head => RefSpec::Rev(rev),
+                    refs => refs,
+                };
+                send_history(&analyzer, &HistoryQuery { refs, ..history }, &spec, until, ages, &tx);
+            });
+
+            return Ok(Self {
+                events,
+                scan_progress,
+                travel: None,
+                detail,
+            });
+        }
+
+        if options.watch {
+            if let Err(err) = spawn_watcher(path.clone(), history.clone(), spec.clone(), markers.clone(), tx.clone()) {
+                let _ = tx.send(LoadEvent::Error(format!("watch failed: {}", err)));
+            }
+        }
+
+        let travel = spawn_travel(path.clone(), history.clone(), spec.clone(), tx.clone());
+
+        let scan_tx = tx.clone();
+        let progress = Arc::clone(&scan_progress);
+        thread::spawn(move || {
+            let event = match scan_directory(&path, &markers, &progress) {
+                Ok(root) => LoadEvent::Tree(root),
+                Err(err) => LoadEvent::Error(format!("scan failed: {}", err)),
+            };
+            let _ = scan_tx.send(event);
+        });
+
+        thread::spawn(move || {
+            // git statusは履歴の解析より速いので先に送る
+            let event = match analyzer.working_tree_status() {
+                Ok(status) => LoadEvent::Status(status),
+                Err(err) => LoadEvent::Error(format!("git status failed: {}", err)),
+            };
+            let _ = tx.send(event);
+
+            send_history(&analyzer, &history, &spec, unix_now(), ages, &tx);
+        });
+
+        Ok(Self {
+            events,
+            scan_progress,
+            travel: Some(travel),
+            detail,
+        })
+    }
+
+    /// 詳細パネルに表示するblameと関数ごとの変更回数を要求する
+    /// （結果は `LoadEvent::Blame` と `LoadEvent::Functions` で届く）
+    pub fn detail(&self, request: DetailRequest) {
+        let _ = self.detail.send(request);
+    }
+
+    /// `offset` 期間前のツリーを要求する（結果は `LoadEvent::Travel` で届く）
+    pub fn travel(&self, offset: usize) {
+        if let Some(travel) = &self.travel {
+            let _ = travel.send(offset);
+        }
+    }
+}
+
+/// オプションと設定ファイルから履歴の走査条件を作る（TUIの履歴と `report` の範囲で同じ条件を使う）
+pub fn history_query(analyzer: &GitAnalyzer, options: &Options) -> Result<HistoryQuery> {
+    Ok(HistoryQuery {
+        refs: options.refs.clone(),
+        merges: options.merges,
+        filter: CommitFilter::new(&options.config.filters, &options.path, analyzer)?,
+        fixes: FixClassifier::new(&options.config.defects.patterns)?,
+        ..HistoryQuery::recent(options.days)
+    })
+}
+
+/// 履歴を解析し、途中経過の履歴メトリクスと完了を送る
+/// `ages` がtrueなら、続けて全履歴から求めた年齢を送る（完了の通知を全履歴の解析で遅らせない）
+fn send_history(
+    analyzer: &GitAnalyzer,
+    query: &HistoryQuery,
+    spec: &HistorySpec,
+    until: i64,
+    ages: bool,
+    tx: &Sender<LoadEvent>,
+) {
+    let result = analyzer.collect_commits(query, |records, done, total| {
+        let metrics = spec.compute(records, until);
+        let records = Arc::new(records.to_vec());
+        let _ = tx.send(LoadEvent::History { metrics, records, done, total });
+    });
+    if let Err(err) = result {
+        let _ = tx.send(LoadEvent::Error(format!("git analysis failed: {}", err)));
+        return;
+    }
+    let _ = tx.send(LoadEvent::GitFinished);
+
+    if ages {
+        // 年齢は期間に関係なく全履歴から求める（解析済みのコミットはキャッシュから読む）
+        let event = match analyzer.collect_commits(&query.full(), |_, _, _| {}) {
+            Ok(records) => LoadEvent::Ages(file_ages(&records)),
+            Err(err) => LoadEvent::Error(format!("age analysis failed: {}", err)),
+        };
+        let _ = tx.send(event);
+    }
+}
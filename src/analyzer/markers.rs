@@ -0,0 +1,63 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// 技術的負債の目印（TODO・FIXMEなど）の一覧
+/// 英数字で始まる・終わる目印は単語の境界でのみ一致させる（`TODOS` や `xxxx` は数えない）
+#[derive(Debug, Clone, Default)]
+pub struct Markers {
+    /// 目印がなければNone
+    pattern: Option<Regex>,
+}
+
+impl Markers {
+    pub fn new(markers: &[String]) -> Result<Self> {
+        let alternatives: Vec<String> = markers
+            .iter()
+            .filter(|marker| !marker.is_empty())
+            .map(|marker| {
+                let is_word = |c: char| c.is_alphanumeric() || c == '_';
+                let start = if marker.starts_with(is_word) { r"\b" } else { "" };
+                let end = if marker.ends_with(is_word) { r"\b" } else { "" };
+                format!("{}{}{}", start, regex::escape(marker), end)
+            })
+            .collect();
+        if alternatives.is_empty() {
+            return Ok(Self::default());
+        }
+        Ok(Self {
+            pattern: Some(Regex::new(&alternatives.join("|"))?),
+        })
+    }
+}
+
+/// 1行ずつ受け取り目印の数を数える（`LineSplitter` が区切った行を渡す）
+#[derive(Debug)]
+pub struct MarkerCounter<'a> {
+    markers: &'a Markers,
+    counts: BTreeMap<String, usize>,
+}
+
+impl<'a> MarkerCounter<'a> {
+    pub fn new(markers: &'a Markers) -> Self {
+        Self {
+            markers,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// 改行を含まない1行を数える
+    pub fn count_line(&mut self, line: &str) {
+        let Some(pattern) = &self.markers.pattern else {
+            return;
+        };
+        for found in pattern.find_iter(line) {
+            *self.counts.entry(found.as_str().to_string()).or_insert(0) += 1;
+        }
+    }
+
+    /// 目印ごとの数を確定する
+    pub fn finish(self) -> BTreeMap<String, usize> {
+        self.counts
+    }
+}
@@ -1,5 +1,22 @@
+pub mod blame;
+pub mod codeowners;
+pub mod compare;
+pub mod complexity;
+pub mod coverage;
+pub mod detail;
+pub mod diagnostics;
+pub mod filter;
+pub mod functions;
 pub mod git;
+pub mod history;
+pub mod loader;
+pub mod markers;
 pub mod scanner;
+#[cfg(test)]
+mod test_repo;
+pub mod travel;
+pub mod watcher;
 
 pub use git::GitAnalyzer;
-pub use scanner::scan_directory;
+pub use loader::{LoadEvent, Loader};
+pub use scanner::{apply_ages, apply_coverage, apply_diagnostics, apply_history, scan_directory, ScanProgress};
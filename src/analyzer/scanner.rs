@@ -1,14 +1,30 @@
-use crate::data::{FileNode, Metrics};
+use super::complexity::ComplexityCounter;
+use super::history::HistoryMetrics;
+use super::markers::{MarkerCounter, Markers};
+use crate::data::{CodeAge, Complexity, Coverage, Diagnostics, FileNode, Metrics, MetricsDelta};
 use anyhow::Result;
-use std::collections::HashMap;
-use std::fs;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-/// ディレクトリをスキャンしてファイルツリーを構築
-pub fn scan_directory(
-    root: &Path,
-    frequency_map: &HashMap<PathBuf, f64>,
-) -> Result<FileNode> {
+/// スキャンの進捗（別スレッドから参照される）
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    files: AtomicUsize,
+}
+
+impl ScanProgress {
+    /// スキャン済みのファイル数
+    pub fn files(&self) -> usize {
+        self.files.load(Ordering::Relaxed)
+    }
+}
+
+/// ディレクトリをスキャンしてファイルツリーを構築（`markers` はファイルの内容から数える目印）
+pub fn scan_directory(root: &Path, markers: &Markers, progress: &ScanProgress) -> Result<FileNode> {
     let mut root_node = FileNode::new(
         root.file_name()
             .unwrap_or_default()
@@ -18,75 +34,278 @@ pub fn scan_directory(
         true,
     );
 
-    build_tree(&mut root_node, root, root, frequency_map)?;
+    build_tree(&mut root_node, root, markers, progress)?;
     root_node.sort_children();
 
     Ok(root_node)
 }
 
-fn build_tree(
-    node: &mut FileNode,
-    root: &Path,
-    current: &Path,
-    frequency_map: &HashMap<PathBuf, f64>,
-) -> Result<()> {
+/// 単一のパスをスキャン（ファイルなら行数、ディレクトリならサブツリーごと）
+pub fn scan_path(path: &Path, markers: &Markers) -> Result<FileNode> {
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let mut node = build_child(name, path.to_path_buf(), markers, &ScanProgress::default())?;
+    node.sort_children();
+    Ok(node)
+}
+
+fn build_tree(node: &mut FileNode, current: &Path, markers: &Markers, progress: &ScanProgress) -> Result<()> {
     if !current.is_dir() {
         return Ok(());
     }
 
-    for entry in fs::read_dir(current)? {
-        let entry = entry?;
-        let path = entry.path();
-        let name = path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
+    let entries = fs::read_dir(current)?.collect::<std::io::Result<Vec<_>>>()?;
 
-        // .gitなどの隠しファイル/ディレクトリをスキップ
-        if name.starts_with('.') {
-            continue;
-        }
+    // エントリ単位でワークスティーリングプールに分配
+    let children = entries
+        .par_iter()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
 
-        let is_dir = path.is_dir();
-        let mut child = FileNode::new(name, path.clone(), is_dir);
+            // .gitなどの隠しファイル/ディレクトリをスキップ
+            if name.starts_with('.') {
+                return None;
+            }
 
-        if is_dir {
-            build_tree(&mut child, root, &path, frequency_map)?;
-            // ディレクトリの場合、子要素のメトリクスを集計
-            child.metrics = aggregate_metrics(&child);
-        } else {
-            // ファイルの場合、行数と変更頻度を取得
-            let lines = count_lines(&path).unwrap_or(0);
-            let relative_path = path.strip_prefix(root).unwrap_or(&path);
-            let frequency = frequency_map
-                .get(relative_path)
-                .copied()
-                .unwrap_or(0.0);
-
-            child.metrics = Metrics::new(lines, frequency);
-        }
+            Some(build_child(name, path, markers, progress))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
+    for child in children {
         node.add_child(child);
     }
 
     Ok(())
 }
 
+fn build_child(name: String, path: PathBuf, markers: &Markers, progress: &ScanProgress) -> Result<FileNode> {
+    let is_dir = path.is_dir();
+    let mut child = FileNode::new(name, path.clone(), is_dir);
+
+    if is_dir {
+        build_tree(&mut child, &path, markers, progress)?;
+        // ディレクトリの場合、子要素のメトリクスを集計
+        child.metrics = aggregate_metrics(&child);
+    } else {
+        // ファイルの場合、行数・複雑さ・目印の数を取得（変更頻度はGit解析後に apply_history で反映）
+        let (lines, complexity, markers) = count_lines(&path, markers).unwrap_or_default();
+        child.metrics = Metrics {
+            complexity,
+            markers,
+            ..Metrics::new(lines, 0.0)
+        };
+        progress.files.fetch_add(1, Ordering::Relaxed);
+    }
+
+    Ok(child)
+}
+
+/// Git解析で得た変更頻度とアクティビティをツリーに反映し、ディレクトリの集計をやり直す
+pub fn apply_history(node: &mut FileNode, root: &Path, history: &HistoryMetrics) {
+    if node.is_dir {
+        for child in &mut node.children {
+            apply_history(child, root, history);
+        }
+        let aggregated = aggregate_metrics(node);
+        node.metrics.lines = aggregated.lines;
+        node.metrics.change_frequency = aggregated.change_frequency;
+        node.metrics.activity = aggregated.activity;
+        node.metrics.commits = aggregated.commits;
+        node.metrics.fixes = aggregated.fixes;
+        node.metrics.complexity = aggregated.complexity;
+        node.metrics.markers = aggregated.markers;
+    } else {
+        let relative_path = node.path.strip_prefix(root).unwrap_or(&node.path);
+        node.metrics.change_frequency = history
+            .frequency
+            .get(relative_path)
+            .copied()
+            .unwrap_or(0.0);
+        node.metrics.activity = history
+            .activity
+            .get(relative_path)
+            .cloned()
+            .unwrap_or_default();
+        node.metrics.commits = history.commits.get(relative_path).copied().unwrap_or(0);
+        node.metrics.fixes = history.fixes.get(relative_path).copied().unwrap_or(0);
+    }
+}
+
+/// 履歴から求めた年齢をツリーに反映する（ディレクトリは配下のファイルの最新・最古・中央値）
+pub fn apply_ages(node: &mut FileNode, root: &Path, ages: &HashMap<PathBuf, CodeAge>) {
+    collect_ages(node, root, ages);
+}
+
+/// 年齢を反映し、配下のファイルの年齢を返す
+fn collect_ages(node: &mut FileNode, root: &Path, ages: &HashMap<PathBuf, CodeAge>) -> Vec<CodeAge> {
+    if !node.is_dir {
+        let relative_path = node.path.strip_prefix(root).unwrap_or(&node.path);
+        node.metrics.age = ages.get(relative_path).copied();
+        return node.metrics.age.into_iter().collect();
+    }
+
+    let mut descendants: Vec<CodeAge> = node
+        .children
+        .iter_mut()
+        .flat_map(|child| collect_ages(child, root, ages))
+        .collect();
+
+    descendants.sort_by_key(|age| age.last_changed);
+    node.metrics.age = descendants.get(descendants.len() / 2).map(|median| CodeAge {
+        last_changed: descendants.iter().map(|age| age.last_changed).max().unwrap_or_default(),
+        first_seen: descendants.iter().map(|age| age.first_seen).min().unwrap_or_default(),
+        median_changed: median.last_changed,
+    });
+    descendants
+}
+
+/// カバレッジレポートの網羅行数をツリーに反映する（ディレクトリは配下の合計）
+/// 戻り値はこのノードの網羅行数
+pub fn apply_coverage(node: &mut FileNode, root: &Path, coverage: &HashMap<PathBuf, Coverage>) -> Option<Coverage> {
+    node.metrics.coverage = if node.is_dir {
+        node.children
+            .iter_mut()
+            .filter_map(|child| apply_coverage(child, root, coverage))
+            .reduce(|total, child| Coverage {
+                covered: total.covered + child.covered,
+                total: total.total + child.total,
+            })
+    } else {
+        let relative_path = node.path.strip_prefix(root).unwrap_or(&node.path);
+        coverage.get(relative_path).copied()
+    };
+    node.metrics.coverage
+}
+
+/// 診断結果のエラー・警告の数をツリーに反映する（ディレクトリは配下の合計）
+/// 戻り値はこのノードのエラー・警告の数
+pub fn apply_diagnostics(
+    node: &mut FileNode,
+    root: &Path,
+    diagnostics: &HashMap<PathBuf, Diagnostics>,
+) -> Diagnostics {
+    node.metrics.diagnostics = if node.is_dir {
+        node.children
+            .iter_mut()
+            .map(|child| apply_diagnostics(child, root, diagnostics))
+            .fold(Diagnostics::default(), |total, child| Diagnostics {
+                errors: total.errors + child.errors,
+                warnings: total.warnings + child.warnings,
+            })
+    } else {
+        let relative_path = node.path.strip_prefix(root).unwrap_or(&node.path);
+        diagnostics.get(relative_path).copied().unwrap_or_default()
+    };
+    node.metrics.diagnostics
+}
+
+/// 比較対象の時点の行数・変更頻度との差分をツリーに付与する
+/// 戻り値はこのノードの比較対象時点でのメトリクス
+pub fn apply_baseline(
+    node: &mut FileNode,
+    root: &Path,
+    lines_map: &HashMap<PathBuf, usize>,
+    frequency_map: &HashMap<PathBuf, f64>,
+) -> Metrics {
+    let baseline = if node.is_dir {
+        let children: Vec<(bool, Metrics)> = node
+            .children
+            .iter_mut()
+            .map(|child| {
+                let is_dir = child.is_dir;
+                (is_dir, apply_baseline(child, root, lines_map, frequency_map))
+            })
+            .collect();
+        aggregate(children.iter().map(|(is_dir, metrics)| (*is_dir, metrics)))
+    } else {
+        let relative_path = node.path.strip_prefix(root).unwrap_or(&node.path);
+        Metrics::new(
+            lines_map.get(relative_path).copied().unwrap_or(0),
+            frequency_map.get(relative_path).copied().unwrap_or(0.0),
+        )
+    };
+
+    node.metrics.delta = Some(MetricsDelta {
+        lines: node.metrics.lines as i64 - baseline.lines as i64,
+        change_frequency: node.metrics.change_frequency - baseline.change_frequency,
+    });
+
+    baseline
+}
+
+/// リポジトリルートからの相対パスと行数の一覧からツリーを構築（Gitのツリーオブジェクト用）
+pub fn build_tree_from_lines(root: &Path, lines_map: &HashMap<PathBuf, usize>) -> FileNode {
+    let mut root_node = FileNode::new(
+        root.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        root.to_path_buf(),
+        true,
+    );
+
+    for (relative_path, lines) in lines_map {
+        let path = root.join(relative_path);
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let mut node = FileNode::new(name, path, false);
+        node.metrics = Metrics::new(*lines, 0.0);
+        root_node.upsert(node);
+    }
+
+    root_node.sort_children();
+    apply_history(&mut root_node, root, &HistoryMetrics::default());
+    root_node
+}
+
 fn aggregate_metrics(node: &FileNode) -> Metrics {
+    aggregate(node.children.iter().map(|child| (child.is_dir, &child.metrics)))
+}
+
+/// 子要素のメトリクスを集計（行数・アクティビティ・コミット数・複雑さ・目印の数は合計、変更頻度は平均）
+fn aggregate<'a>(children: impl Iterator<Item = (bool, &'a Metrics)>) -> Metrics {
     let mut total_lines = 0;
     let mut total_frequency = 0.0;
     let mut file_count = 0;
+    let mut activity: Vec<u32> = Vec::new();
+    let mut commits = 0;
+    let mut fixes = 0;
+    let mut complexity = Complexity::default();
+    let mut markers: BTreeMap<String, usize> = BTreeMap::new();
 
-    for child in &node.children {
-        total_lines += child.metrics.lines;
-        if !child.is_dir {
-            total_frequency += child.metrics.change_frequency;
+    for (is_dir, metrics) in children {
+        complexity.add(&metrics.complexity);
+        for (marker, count) in &metrics.markers {
+            *markers.entry(marker.clone()).or_insert(0) += count;
+        }
+        total_lines += metrics.lines;
+        commits += metrics.commits;
+        fixes += metrics.fixes;
+        if activity.len() < metrics.activity.len() {
+            activity.resize(metrics.activity.len(), 0);
+        }
+        for (total, count) in activity.iter_mut().zip(&metrics.activity) {
+            *total += count;
+        }
+        if !is_dir {
+            total_frequency += metrics.change_frequency;
             file_count += 1;
         } else {
             // 再帰的にディレクトリの平均も考慮
-            total_frequency += child.metrics.change_frequency;
-            if child.metrics.lines > 0 {
+            total_frequency += metrics.change_frequency;
+            if metrics.lines > 0 {
                 file_count += 1;
             }
         }
@@ -98,10 +317,159 @@ fn aggregate_metrics(node: &FileNode) -> Metrics {
         0.0
     };
 
-    Metrics::new(total_lines, avg_frequency)
+    Metrics {
+        activity,
+        commits,
+        fixes,
+        complexity,
+        markers,
+        ..Metrics::new(total_lines, avg_frequency)
+    }
+}
+
+/// ファイル全体をメモリに載せずにバイト単位で行数・複雑さ・目印の数を数える
+/// NULバイトを含むファイルはバイナリとみなして0行とする
+fn count_lines(path: &Path, markers: &Markers) -> Result<(usize, Complexity, BTreeMap<String, usize>)> {
+    let mut file = File::open(path)?;
+    let mut buf = [0u8; 64 * 1024];
+    let mut splitter = LineSplitter::default();
+    let mut complexity = ComplexityCounter::for_path(path);
+    let mut marker_counter = MarkerCounter::new(markers);
+    let mut count_line = |line: &str| {
+        complexity.count_line(line);
+        marker_counter.count_line(line);
+    };
+
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        splitter.feed(&buf[..read], &mut count_line);
+        if splitter.is_binary() {
+            return Ok((0, Complexity::default(), BTreeMap::new()));
+        }
+    }
+
+    let lines = splitter.finish(&mut count_line);
+    Ok((lines, complexity.finish(), marker_counter.finish()))
+}
+
+/// チャンク単位でバイト列を受け取り、改行で区切った行を渡しながら行数を数える
+#[derive(Debug, Default)]
+pub struct LineSplitter {
+    /// チャンクの境界で途切れた行
+    pending: Vec<u8>,
+    lines: usize,
+    binary: bool,
+}
+
+impl LineSplitter {
+    /// チャンクのうち改行で終わった行を1行ずつ（改行を除いて）`on_line` に渡す
+    /// NULバイトを含むチャンクを受け取った後はバイナリとみなして何もしない
+    pub fn feed(&mut self, chunk: &[u8], mut on_line: impl FnMut(&str)) {
+        if self.binary || chunk.contains(&0) {
+            self.binary = true;
+            return;
+        }
+        let Some(end) = chunk.iter().rposition(|&b| b == b'\n') else {
+            self.pending.extend_from_slice(chunk);
+            return;
+        };
+
+        for (index, line) in chunk[..end].split(|&b| b == b'\n').enumerate() {
+            self.lines += 1;
+            // 最初の行は前のチャンクの途切れた行の続き
+            if index == 0 && !self.pending.is_empty() {
+                self.pending.extend_from_slice(line);
+                on_line(&String::from_utf8_lossy(&self.pending));
+                self.pending.clear();
+            } else {
+                on_line(&String::from_utf8_lossy(line));
+            }
+        }
+        self.pending.extend_from_slice(&chunk[end + 1..]);
+    }
+
+    /// NULバイトを含む（バイナリとみなす）か
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+
+    /// 行数を確定する
+    /// 末尾に改行がない最終行も1行として渡して数える（str::linesと同じ挙動）
+    pub fn finish(mut self, mut on_line: impl FnMut(&str)) -> usize {
+        if self.binary {
+            return 0;
+        }
+        if !self.pending.is_empty() {
+            self.lines += 1;
+            on_line(&String::from_utf8_lossy(&self.pending));
+        }
+        self.lines
+    }
 }
 
-fn count_lines(path: &Path) -> Result<usize> {
-    let content = fs::read_to_string(path)?;
-    Ok(content.lines().count())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// チャンクに分けて渡したときの行と行数
+    fn split(chunks: &[&str]) -> (Vec<String>, usize) {
+        let mut splitter = LineSplitter::default();
+        let mut lines = Vec::new();
+        for chunk in chunks {
+            splitter.feed(chunk.as_bytes(), |line| lines.push(line.to_string()));
+        }
+        let count = splitter.finish(|line| lines.push(line.to_string()));
+        (lines, count)
+    }
+
+    #[test]
+    fn joins_lines_split_across_chunks() {
+        let expected = (vec!["fn main() {".to_string(), "".to_string(), "}".to_string()], 3);
+        assert_eq!(split(&["fn main() {\n\n}\n"]), expected);
+        assert_eq!(split(&["fn ma", "in() {\n", "\n}", "\n"]), expected);
+        assert_eq!(split(&["fn main", "() {", "\n\n}"]), expected);
+    }
+
+    #[test]
+    fn counts_like_str_lines() {
+        for text in ["", "\n", "a", "a\n", "a\nb", "a\r\nb\r\n", "\n\n"] {
+            let (lines, count) = split(&[text]);
+            assert_eq!(count, text.lines().count(), "{:?}", text);
+            assert_eq!(lines.len(), count, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn treats_nul_bytes_as_binary() {
+        let mut splitter = LineSplitter::default();
+        splitter.feed(b"text\n", |_| {});
+        splitter.feed(b"\0\n", |_| panic!("binary chunks are not split"));
+        assert!(splitter.is_binary());
+        assert_eq!(splitter.finish(|_| {}), 0);
+    }
+
+    #[test]
+    fn colours_directories_by_their_most_complex_file() {
+        let file = |cyclomatic: usize| Metrics {
+            complexity: Complexity {
+                cyclomatic: Some(cyclomatic),
+                cyclomatic_max: Some(cyclomatic),
+                ..Complexity::default()
+            },
+            ..Metrics::new(10, 0.0)
+        };
+        let files: Vec<Metrics> = (0..12).map(|_| file(9)).collect();
+        let directory = aggregate(files.iter().map(|metrics| (false, metrics)));
+        // 合計は108だが、どのファイルも10未満なので最も低いカテゴリ
+        assert_eq!(directory.complexity.cyclomatic, Some(108));
+        assert_eq!(directory.complexity.cyclomatic_category(), Some(0));
+
+        let complex = file(120);
+        let parent = aggregate([(true, &directory), (false, &complex), (false, &Metrics::default())].into_iter());
+        assert_eq!(parent.complexity.cyclomatic_max, Some(120));
+        assert_eq!(parent.complexity.cyclomatic_category(), Some(4));
+    }
 }
@@ -0,0 +1,54 @@
+use git2::{Oid, Repository, Signature, Time};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 一時ディレクトリに作ったリポジトリ（テストの終わりに削除する）
+pub struct TestRepo {
+    pub dir: PathBuf,
+    pub repo: Repository,
+    time: i64,
+}
+
+impl TestRepo {
+    /// `name` はテストごとに重ならない名前にする
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("heatree-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        let repo = Repository::init(&dir).unwrap();
+        Self { dir, repo, time: 1_700_000_000 }
+    }
+
+    /// ファイルを書いてコミットする（コミット日時は1時間ずつ進む）
+    pub fn commit(&mut self, path: &str, content: &str) -> Oid {
+        self.commit_files(&[(path, content)])
+    }
+
+    /// 複数のファイルを書いて1つのコミットにする
+    pub fn commit_files(&mut self, files: &[(&str, &str)]) -> Oid {
+        let mut index = self.repo.index().unwrap();
+        for (path, content) in files {
+            let file = self.dir.join(path);
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(file, content).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = self.repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+        self.time += 3600;
+        let signature = Signature::new("dev", "dev@example.com", &Time::new(self.time, 0)).unwrap();
+        let parent = self.repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        self.repo
+            .commit(Some("HEAD"), &signature, &signature, "change", &tree, &parents)
+            .unwrap()
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,47 @@
+use super::compare::build_tree_at;
+use super::git::HistoryQuery;
+use super::history::{unix_now, HistorySpec, SECONDS_PER_DAY};
+use super::loader::LoadEvent;
+use super::GitAnalyzer;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+/// タイムトラベル用のツリーを構築するスレッドを開始し、要求（何期間前か）の送信先を返す
+/// 期間は `spec.days` 日ごとに区切り、0は直近の期間を表す
+pub fn spawn_travel(
+    root: PathBuf,
+    history: HistoryQuery,
+    spec: HistorySpec,
+    tx: Sender<LoadEvent>,
+) -> Sender<usize> {
+    let (request_tx, request_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let analyzer = match GitAnalyzer::new(&root) {
+            Ok(analyzer) => analyzer,
+            Err(err) => {
+                let _ = tx.send(LoadEvent::Error(format!("time travel failed: {}", err)));
+                return;
+            }
+        };
+
+        while let Ok(mut offset) = request_rx.recv() {
+            // キーを連打した場合は途中の期間を飛ばして最新の要求だけを処理する
+            while let Ok(latest) = request_rx.try_recv() {
+                offset = latest;
+            }
+
+            let until = unix_now() - (offset * spec.days) as i64 * SECONDS_PER_DAY;
+            let event = match build_tree_at(&analyzer, &root, &history, &spec, until) {
+                Ok(tree) => LoadEvent::Travel { offset, tree },
+                Err(err) => LoadEvent::Error(format!("time travel failed: {}", err)),
+            };
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+    });
+
+    request_tx
+}
@@ -0,0 +1,149 @@
+use super::git::HistoryQuery;
+use super::history::{file_ages, unix_now, HistorySpec};
+use super::loader::LoadEvent;
+use super::markers::Markers;
+use super::scanner::scan_path;
+use super::GitAnalyzer;
+use anyhow::Result;
+use notify::{RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// 連続したファイルシステムイベントをまとめる待ち時間
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 作業ツリーと.gitを監視し、変更をLoadEventとして送る
+pub fn spawn_watcher(
+    root: PathBuf,
+    history: HistoryQuery,
+    spec: HistorySpec,
+    markers: Markers,
+    tx: Sender<LoadEvent>,
+) -> Result<()> {
+    let (fs_tx, fs_rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(fs_tx)?;
+    watcher.watch(&root, RecursiveMode::Recursive)?;
+    let analyzer = GitAnalyzer::new(&root)?;
+
+    thread::spawn(move || {
+        // スレッドが生きている間はウォッチャーを保持する
+        let _watcher = watcher;
+
+        while let Ok(first) = fs_rx.recv() {
+            let mut changed = HashSet::new();
+            let mut git_changed = false;
+            let mut index_changed = false;
+
+            // デバウンス期間内のイベントをまとめて処理
+            let mut pending = Some(first);
+            while let Some(event) = pending {
+                if let Ok(event) = event {
+                    for path in event.paths {
+                        match classify(&root, &path) {
+                            Change::WorkTree => {
+                                changed.insert(path);
+                            }
+                            Change::GitRefs => git_changed = true,
+                            Change::Index => index_changed = true,
+                            Change::Ignored => {}
+                        }
+                    }
+                }
+                pending = fs_rx.recv_timeout(DEBOUNCE).ok();
+            }
+
+            let worktree_changed = !changed.is_empty();
+            for path in changed {
+                let node = if path.exists() {
+                    match scan_path(&path, &markers) {
+                        Ok(node) => Some(node),
+                        Err(_) => continue,
+                    }
+                } else {
+                    None
+                };
+                if tx.send(LoadEvent::PathChanged(path, node)).is_err() {
+                    return;
+                }
+            }
+
+            // ファイルの編集・ステージ・コミットのいずれでも状態が変わり得る
+            if worktree_changed || index_changed || git_changed {
+                if let Ok(status) = analyzer.working_tree_status() {
+                    if tx.send(LoadEvent::Status(status)).is_err() {
+                        return;
+                    }
+                }
+            }
+
+            // 新しいコミットはキャッシュにない分だけ解析される
+            if git_changed {
+                let result = analyzer.collect_commits(&history, |_, _, _| {});
+                let event = match result {
+                    Ok(records) => LoadEvent::History {
+                        metrics: spec.compute(&records, unix_now()),
+                        done: records.len(),
+                        total: records.len(),
+                        records: Arc::new(records),
+                    },
+                    Err(err) => LoadEvent::Error(format!("git analysis failed: {}", err)),
+                };
+                if tx.send(event).is_err() {
+                    return;
+                }
+
+                if let Ok(records) = analyzer.collect_commits(&history.full(), |_, _, _| {}) {
+                    if tx.send(LoadEvent::Ages(file_ages(&records))).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+enum Change {
+    /// 作業ツリー内のファイル・ディレクトリ
+    WorkTree,
+    /// ブランチやHEADの移動（新しいコミット）
+    GitRefs,
+    /// インデックスの更新（git add など）
+    Index,
+    Ignored,
+}
+
+fn classify(root: &Path, path: &Path) -> Change {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return Change::Ignored;
+    };
+
+    let mut components = relative.components().filter_map(|component| match component {
+        Component::Normal(name) => Some(name.to_string_lossy()),
+        _ => None,
+    });
+
+    match components.next() {
+        Some(first) if first == ".git" => match components.next().as_deref() {
+            // .git/heatree はこのツール自身のキャッシュなので無視する
+            Some("HEAD") | Some("refs") | Some("packed-refs") => Change::GitRefs,
+            // ステージの変更はgit statusの更新だけでよい
+            Some("index") => Change::Index,
+            _ => Change::Ignored,
+        },
+        None => Change::Ignored,
+        // スキャン時と同様に隠しファイル/ディレクトリは対象外
+        Some(_) if relative
+            .components()
+            .any(|component| component.as_os_str().to_string_lossy().starts_with('.')) =>
+        {
+            Change::Ignored
+        }
+        Some(_) => Change::WorkTree,
+    }
+}
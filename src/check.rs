@@ -0,0 +1,326 @@
+use crate::config::CheckRule;
+use crate::data::FileNode;
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+
+/// ルールに違反したノード
+#[derive(Debug, Clone)]
+pub struct Violation {
+    /// ルールの名前
+    pub rule: String,
+    /// リポジトリルートからの相対パス
+    pub path: PathBuf,
+    /// 超えた上限・下限ごとの説明（例: `lines 1034 > 1000`）
+    pub reasons: Vec<String>,
+}
+
+/// ノードから値を取り出す（判定できないノードはNone）
+type Measure = fn(&FileNode) -> Option<f64>;
+
+/// ルールに設定された上限または下限の1つ
+struct Limit {
+    label: &'static str,
+    threshold: f64,
+    /// trueなら下限（値が下回れば超えたとみなす）
+    minimum: bool,
+    precision: usize,
+    unit: &'static str,
+    measure: Measure,
+}
+
+impl Limit {
+    /// 設定されていない上限・下限はNone
+    fn new(
+        label: &'static str,
+        threshold: Option<f64>,
+        minimum: bool,
+        precision: usize,
+        unit: &'static str,
+        measure: Measure,
+    ) -> Option<Self> {
+        threshold.map(|threshold| Self {
+            label,
+            threshold,
+            minimum,
+            precision,
+            unit,
+            measure,
+        })
+    }
+
+    /// 値が上限・下限を超えていれば説明を返す
+    fn check(&self, node: &FileNode) -> Option<String> {
+        let value = (self.measure)(node)?;
+        let exceeded = if self.minimum { value < self.threshold } else { value > self.threshold };
+        exceeded.then(|| {
+            format!(
+                "{} {:.precision$}{unit} {} {:.precision$}{unit}",
+                self.label,
+                value,
+                if self.minimum { "<" } else { ">" },
+                self.threshold,
+                precision = self.precision,
+                unit = self.unit,
+            )
+        })
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} {} {:.precision$}{unit}",
+            self.label,
+            if self.minimum { "<" } else { ">" },
+            self.threshold,
+            precision = self.precision,
+            unit = self.unit,
+        )
+    }
+}
+
+/// ルールに設定された上限・下限
+fn rule_limits(rule: &CheckRule) -> Vec<Limit> {
+    let count = |max: Option<usize>| max.map(|max| max as f64);
+    [
+        Limit::new("lines", count(rule.max_lines), false, 0, "", |node| Some(node.metrics.lines as f64)),
+        Limit::new("changes/day", rule.max_frequency, false, 2, "", |node| {
+            Some(node.metrics.change_frequency)
+        }),
+        Limit::new("hotspot score", rule.max_score, false, 1, "", |node| Some(node.hotspot_score())),
+        Limit::new("commits", count(rule.max_commits), false, 0, "", |node| {
+            Some(node.metrics.commits as f64)
+        }),
+        Limit::new("fixes", rule.max_fix_percent, false, 1, "%", |node| {
+            Some(node.metrics.fix_ratio() * 100.0)
+        }),
+        Limit::new("cyclomatic", count(rule.max_cyclomatic), false, 0, "", |node| {
+            node.metrics.complexity.cyclomatic.map(|cyclomatic| cyclomatic as f64)
+        }),
+        Limit::new("markers", count(rule.max_markers), false, 0, "", |node| {
+            Some(node.metrics.marker_count() as f64)
+        }),
+        Limit::new("diagnostics", count(rule.max_diagnostics), false, 0, "", |node| {
+            Some(node.metrics.diagnostics.total() as f64)
+        }),
+        Limit::new("coverage", rule.min_coverage, true, 1, "%", |node| {
+            node.metrics.coverage.map(|coverage| coverage.ratio() * 100.0)
+        }),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// ツリーに対してルールを評価し、違反をルールの順に返す
+/// ルールの対象が見つからない場合や、上限・下限を1つも設定していないルールはエラーにする
+pub fn run_checks(root: &FileNode, rules: &[CheckRule]) -> Result<Vec<Violation>> {
+    let mut violations = Vec::new();
+
+    for (index, rule) in rules.iter().enumerate() {
+        let limits = rule_limits(rule);
+        let name = rule.name.clone().unwrap_or_else(|| {
+            let conditions: Vec<String> = limits.iter().map(Limit::describe).collect();
+            conditions.join(" and ")
+        });
+        if limits.is_empty() {
+            bail!("check rule #{} ({}) sets no limits", index + 1, name);
+        }
+
+        let target_path = match &rule.path {
+            Some(path) => root.path.join(path),
+            None => root.path.clone(),
+        };
+        let Some(target) = root.find(&target_path) else {
+            bail!(
+                "check rule '{}' targets a path that does not exist: {}",
+                name,
+                relative_path(&target_path, &root.path).display()
+            );
+        };
+
+        let mut nodes = Vec::new();
+        if rule.each_file {
+            target.collect_files(&mut nodes);
+        } else {
+            nodes.push(target);
+        }
+
+        for node in nodes {
+            // すべての上限・下限を超えた場合だけ違反とする
+            let reasons: Option<Vec<String>> = limits.iter().map(|limit| limit.check(node)).collect();
+            if let Some(reasons) = reasons {
+                violations.push(Violation {
+                    rule: name.clone(),
+                    path: relative_path(&node.path, &root.path),
+                    reasons,
+                });
+            }
+        }
+    }
+
+    Ok(violations)
+}
+
+/// 表示用の相対パス（ルート自身は `.`）
+fn relative_path(path: &Path, root: &Path) -> PathBuf {
+    match path.strip_prefix(root) {
+        Ok(relative) if relative.as_os_str().is_empty() => PathBuf::from("."),
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Complexity, Coverage, Diagnostics, Metrics};
+    use std::collections::BTreeMap;
+
+    /// src/a.rs・src/b.rs・README.md からなるツリー（ディレクトリには配下の集計値を入れる）
+    fn tree() -> FileNode {
+        let mut root = FileNode::new("repo".to_string(), PathBuf::from("/repo"), true);
+        let files = [
+            (
+                "src/a.rs",
+                Metrics {
+                    commits: 10,
+                    fixes: 6,
+                    complexity: Complexity {
+                        cyclomatic: Some(30),
+                        ..Complexity::default()
+                    },
+                    coverage: Some(Coverage { covered: 40, total: 100 }),
+                    diagnostics: Diagnostics { errors: 1, warnings: 2 },
+                    markers: BTreeMap::from([("TODO".to_string(), 3)]),
+                    ..Metrics::new(1200, 0.5)
+                },
+            ),
+            (
+                "src/b.rs",
+                Metrics {
+                    commits: 2,
+                    complexity: Complexity {
+                        cyclomatic: Some(5),
+                        ..Complexity::default()
+                    },
+                    coverage: Some(Coverage { covered: 90, total: 100 }),
+                    ..Metrics::new(300, 0.1)
+                },
+            ),
+            ("README.md", Metrics { commits: 1, ..Metrics::new(50, 0.05) }),
+        ];
+        for (path, metrics) in files {
+            let path = Path::new("/repo").join(path);
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let mut node = FileNode::new(name, path, false);
+            node.metrics = metrics;
+            root.upsert(node);
+        }
+        root.find_mut(Path::new("/repo/src")).unwrap().metrics = Metrics {
+            commits: 12,
+            ..Metrics::new(1500, 0.3)
+        };
+        root.metrics = Metrics {
+            commits: 13,
+            ..Metrics::new(1550, 0.3)
+        };
+        root
+    }
+
+    /// 各ファイルにルールを1つ適用した違反（パスと理由）
+    fn violations(rule: CheckRule) -> Vec<(String, Vec<String>)> {
+        let rule = CheckRule { each_file: true, ..rule };
+        run_checks(&tree(), &[rule])
+            .unwrap()
+            .into_iter()
+            .map(|violation| (violation.path.display().to_string(), violation.reasons))
+            .collect()
+    }
+
+    fn violation(path: &str, reason: &str) -> Vec<(String, Vec<String>)> {
+        vec![(path.to_string(), vec![reason.to_string()])]
+    }
+
+    #[test]
+    fn checks_each_limit() {
+        let cases = [
+            (CheckRule { max_lines: Some(1000), ..CheckRule::default() }, "lines 1200 > 1000"),
+            (CheckRule { max_frequency: Some(0.2), ..CheckRule::default() }, "changes/day 0.50 > 0.20"),
+            (CheckRule { max_score: Some(100.0), ..CheckRule::default() }, "hotspot score 600.0 > 100.0"),
+            (CheckRule { max_commits: Some(5), ..CheckRule::default() }, "commits 10 > 5"),
+            (CheckRule { max_fix_percent: Some(50.0), ..CheckRule::default() }, "fixes 60.0% > 50.0%"),
+            (CheckRule { max_cyclomatic: Some(10), ..CheckRule::default() }, "cyclomatic 30 > 10"),
+            (CheckRule { max_markers: Some(0), ..CheckRule::default() }, "markers 3 > 0"),
+            (CheckRule { max_diagnostics: Some(2), ..CheckRule::default() }, "diagnostics 3 > 2"),
+            (CheckRule { min_coverage: Some(80.0), ..CheckRule::default() }, "coverage 40.0% < 80.0%"),
+        ];
+        for (rule, reason) in cases {
+            assert_eq!(violations(rule), violation("src/a.rs", reason));
+        }
+    }
+
+    #[test]
+    fn skips_files_without_a_measure() {
+        // README.md は循環的複雑度もカバレッジもない
+        assert!(violations(CheckRule { max_cyclomatic: Some(0), ..CheckRule::default() })
+            .iter()
+            .all(|(path, _)| path != "README.md"));
+        assert!(violations(CheckRule { min_coverage: Some(100.0), ..CheckRule::default() })
+            .iter()
+            .all(|(path, _)| path != "README.md"));
+    }
+
+    #[test]
+    fn requires_every_limit_to_be_exceeded() {
+        let rule = CheckRule {
+            max_lines: Some(200),
+            max_commits: Some(5),
+            ..CheckRule::default()
+        };
+        assert_eq!(
+            violations(rule),
+            vec![(
+                "src/a.rs".to_string(),
+                vec!["lines 1200 > 200".to_string(), "commits 10 > 5".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn checks_the_target_itself_unless_each_file() {
+        let rule = CheckRule {
+            name: Some("big dir".to_string()),
+            path: Some(PathBuf::from("src")),
+            max_lines: Some(1000),
+            ..CheckRule::default()
+        };
+        let violations = run_checks(&tree(), &[rule]).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "big dir");
+        assert_eq!(violations[0].path, PathBuf::from("src"));
+        assert_eq!(violations[0].reasons, vec!["lines 1500 > 1000".to_string()]);
+    }
+
+    #[test]
+    fn names_rules_after_their_limits() {
+        let rule = CheckRule {
+            max_lines: Some(1000),
+            max_frequency: Some(0.25),
+            ..CheckRule::default()
+        };
+        let violations = run_checks(&tree(), &[rule]).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "lines > 1000 and changes/day > 0.25");
+        assert_eq!(violations[0].path, PathBuf::from("."));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(run_checks(&tree(), &[CheckRule::default()]).is_err());
+        let missing = CheckRule {
+            path: Some(PathBuf::from("lib")),
+            max_lines: Some(1),
+            ..CheckRule::default()
+        };
+        assert!(run_checks(&tree(), &[missing]).is_err());
+    }
+}
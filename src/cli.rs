@@ -0,0 +1,224 @@
+use crate::analyzer::compare::CompareSpec;
+use crate::analyzer::git::{MergePolicy, RefSpec};
+use crate::analyzer::history::ActivityBucket;
+use crate::config::{Config, FiltersConfig};
+use anyhow::{bail, Context, Result};
+use std::env;
+use std::path::PathBuf;
+
+/// 実行するサブコマンド（最初の引数で指定する、省略時はTUI）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Command {
+    #[default]
+    Tui,
+    /// 設定ファイルのルールを評価し、違反があれば失敗する
+    Check,
+    /// ホットスポットなどをまとめたMarkdownのレポートを出力する
+    Report,
+}
+
+/// コマンドライン引数
+#[derive(Debug)]
+pub struct Options {
+    pub command: Command,
+    /// 解析対象のリポジトリ（デフォルトはカレントディレクトリ）
+    pub path: PathBuf,
+    /// 変更頻度を計算する期間（日数）
+    pub days: usize,
+    /// 指定すると変更頻度を指数減衰で重み付けする（重みが半分になる日数）
+    pub half_life: Option<f64>,
+    /// アクティビティ（スパークライン）を集計する単位
+    pub bucket: ActivityBucket,
+    /// 作業ツリーと.gitを監視して自動で再解析する
+    pub watch: bool,
+    /// 2時点を比較して差分を表示する
+    pub compare: Option<CompareSpec>,
+    /// 作業ツリーの代わりに表示するリビジョン（チェックアウトは不要）
+    pub rev: Option<String>,
+    /// 変更頻度を計算する履歴の範囲（デフォルトはHEAD）
+    pub refs: RefSpec,
+    /// マージコミットの数え方
+    pub merges: MergePolicy,
+    /// TUIを起動せずに全ファイルのblameの集計をJSONに書き出す
+    pub export_blame: Option<PathBuf>,
+    /// 重ねて表示するカバレッジレポート（lcov・Cobertura XML・llvm-covのJSON）
+    pub coverage: Option<PathBuf>,
+    /// 重ねて表示するコンパイラ・リンターの診断結果（cargoのJSON・SARIF・ESLintのJSON、複数指定可）
+    pub diagnostics: Vec<PathBuf>,
+    /// レポートの各表に載せるファイル数
+    pub top: usize,
+    /// レポートの出力先（省略時は標準出力）
+    pub output: Option<PathBuf>,
+    /// 設定ファイル（`--config` またはリポジトリルートの `.heatree.toml`）
+    pub config: Config,
+}
+
+impl Options {
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(env::args().skip(1))
+    }
+
+    /// 引数の列（プログラム名を除く）を解析
+    pub fn parse_from(args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut args = args.peekable();
+        let command = match args.peek().map(String::as_str) {
+            Some("check") => Command::Check,
+            Some("report") => Command::Report,
+            _ => Command::Tui,
+        };
+        if command != Command::Tui {
+            args.next();
+        }
+
+        let mut path = None;
+        let mut days = 30;
+        let mut half_life = None;
+        let mut bucket = ActivityBucket::default();
+        let mut watch = false;
+        let mut compare = None;
+        let mut rev = None;
+        let mut refs = RefSpec::Head;
+        let mut merges = MergePolicy::default();
+        let mut export_blame = None;
+        let mut coverage = None;
+        let mut diagnostics = Vec::new();
+        let mut top = None;
+        let mut output = None;
+        let mut config_path = None;
+        let mut filters = FiltersConfig::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-w" | "--watch" => watch = true,
+                "--days" => days = parse_number(&arg, args.next())?,
+                "--half-life" => {
+                    let value = args.next().unwrap_or_default();
+                    match value.parse::<f64>() {
+                        Ok(days) if days > 0.0 => half_life = Some(days),
+                        _ => bail!("--half-life requires a positive number of days: {}", value),
+                    }
+                }
+                "--bucket" => {
+                    let value = args.next().unwrap_or_default();
+                    bucket = ActivityBucket::parse(&value)
+                        .with_context(|| format!("--bucket requires day or week: {}", value))?;
+                }
+                "--compare" => {
+                    let range = args.next().context("--compare requires OLD..NEW")?;
+                    compare = Some(CompareSpec::parse_range(&range)?);
+                }
+                "--rev" => rev = Some(args.next().context("--rev requires a revision")?),
+                "--branch" => refs = RefSpec::Rev(args.next().context("--branch requires a branch name")?),
+                "--range" => {
+                    let range = args.next().context("--range requires BASE..TIP")?;
+                    if !range.contains("..") {
+                        bail!("invalid range (expected BASE..TIP): {}", range);
+                    }
+                    refs = RefSpec::Range(range);
+                }
+                "--all-branches" => refs = RefSpec::AllBranches,
+                "--all-refs" => refs = RefSpec::AllRefs,
+                "--merges" => {
+                    let value = args.next().unwrap_or_default();
+                    merges = MergePolicy::parse(&value).with_context(|| {
+                        format!(
+                            "--merges requires one of parent, skip, first-parent, all-parents: {}",
+                            value
+                        )
+                    })?;
+                }
+                "--export-blame" => {
+                    export_blame = Some(PathBuf::from(args.next().context("--export-blame requires an output file")?));
+                }
+                "--coverage" => {
+                    coverage = Some(PathBuf::from(args.next().context("--coverage requires a report file")?));
+                }
+                "--diagnostics" => {
+                    diagnostics.push(PathBuf::from(args.next().context("--diagnostics requires a file")?));
+                }
+                "--author" => filters.include_authors.push(args.next().context("--author requires a pattern")?),
+                "--exclude-author" => {
+                    filters.exclude_authors.push(args.next().context("--exclude-author requires a pattern")?)
+                }
+                "--message" => filters.include_messages.push(args.next().context("--message requires a pattern")?),
+                "--exclude-message" => {
+                    filters.exclude_messages.push(args.next().context("--exclude-message requires a pattern")?)
+                }
+                "--exclude-path" => {
+                    filters.exclude_paths.push(args.next().context("--exclude-path requires a pattern")?)
+                }
+                "--ignore-revs" => {
+                    filters.ignore_revs_file = Some(PathBuf::from(args.next().context("--ignore-revs requires a file")?));
+                }
+                "--max-files" => filters.max_files = Some(parse_number(&arg, args.next())?),
+                "--top" => top = Some(parse_number(&arg, args.next())?),
+                "--output" => output = Some(PathBuf::from(args.next().context("--output requires a file")?)),
+                "--config" => {
+                    config_path = Some(PathBuf::from(args.next().context("--config requires a file")?));
+                }
+                "--compare-days" => {
+                    compare = Some(CompareSpec::Periods {
+                        days: parse_number(&arg, args.next())?,
+                    });
+                }
+                other if other.starts_with('-') => bail!("unknown option: {}", other),
+                _ => path = Some(PathBuf::from(arg)),
+            }
+        }
+
+        if watch && command != Command::Tui {
+            bail!("--watch can only be used with the TUI");
+        }
+        // レポートでは範囲の両端を比較して、範囲内で増えた行数を求める（範囲の終わりが時点になるので --rev とは併用できない）
+        if let (Command::Report, RefSpec::Range(range)) = (command, &refs) {
+            if rev.is_some() {
+                bail!("--range cannot be combined with --rev in the report command");
+            }
+            if compare.is_none() {
+                compare = Some(CompareSpec::parse_range(range)?);
+            }
+        }
+        if [watch, compare.is_some(), rev.is_some()].iter().filter(|&&set| set).count() > 1 {
+            bail!("--watch, --compare and --rev cannot be combined");
+        }
+        if (top.is_some() || output.is_some()) && command != Command::Report {
+            bail!("--top and --output can only be used with the report command");
+        }
+
+        // 監視イベントのパスと一致させるため絶対パスにしておく
+        let path = match path {
+            Some(path) => path.canonicalize()?,
+            None => env::current_dir()?,
+        };
+        let mut config = Config::load(&path, config_path.as_deref())?;
+        config.filters.merge(filters);
+
+        Ok(Self {
+            command,
+            path,
+            days,
+            half_life,
+            bucket,
+            watch,
+            compare,
+            rev,
+            refs,
+            merges,
+            export_blame,
+            coverage,
+            diagnostics,
+            top: top.unwrap_or(10),
+            output,
+            config,
+        })
+    }
+}
+
+/// 1以上の整数を取る引数を解析
+fn parse_number(name: &str, value: Option<String>) -> Result<usize> {
+    let value = value.with_context(|| format!("{} requires a number", name))?;
+    match value.parse() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => bail!("{} requires a positive number: {}", name, value),
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// リポジトリルートに置く設定ファイル
+pub const CONFIG_FILE: &str = ".heatree.toml";
+
+/// 設定ファイルの内容（省略した項目はデフォルト値）
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub defects: DefectsConfig,
+    pub filters: FiltersConfig,
+    pub owners: OwnersConfig,
+    pub markers: MarkersConfig,
+    pub check: CheckConfig,
+}
+
+/// 修正コミットの判定条件
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DefectsConfig {
+    /// 修正コミットとみなすコミットメッセージの正規表現（いずれかに一致すれば修正）
+    /// 例: conventional commitsの `fix:`、"bug" などの単語、`PROJ-123` のような課題キー
+    pub patterns: Vec<String>,
+}
+
+/// 履歴から数えないコミットの条件（正規表現はいずれかに一致すれば該当）
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FiltersConfig {
+    /// 数える作者（`名前 <メール>` に対する正規表現、空ならすべて）
+    pub include_authors: Vec<String>,
+    /// 数えない作者（例: `\[bot\]`）
+    pub exclude_authors: Vec<String>,
+    /// 数えるコミットメッセージ（空ならすべて）
+    pub include_messages: Vec<String>,
+    /// 数えないコミットメッセージ（例: `^chore\(release\)`）
+    pub exclude_messages: Vec<String>,
+    /// 数えないファイル（リポジトリルートからの相対パスに対する正規表現）
+    pub exclude_paths: Vec<String>,
+    /// 無視するコミットを列挙したファイル（`.git-blame-ignore-revs` 形式）
+    pub ignore_revs_file: Option<PathBuf>,
+    /// これより多くのファイルを変更したコミットは数えない
+    pub max_files: Option<usize>,
+}
+
+/// CODEOWNERSの所有者と実際のコミッターを突き合わせる設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OwnersConfig {
+    /// `@ユーザー名` のメールアドレス（例: `"@alice" = ["alice@example.com"]`）
+    /// メールアドレスがわからない所有者のファイルは、最も多くコミットした作者が所有者かどうかを判定しない
+    pub handles: HashMap<String, Vec<String>>,
+    /// チーム（`@org/team`）のメンバー（`@ユーザー名` またはメールアドレス）
+    /// 定義していないチームや、メールアドレスがわからないメンバーを含むチームも判定しない
+    pub teams: HashMap<String, Vec<String>>,
+    /// 期間内にこの回数以上変更されたファイルだけ、所有者がいないことを問題にする
+    pub hot_commits: usize,
+}
+
+/// ファイルの内容から数える技術的負債の目印
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkersConfig {
+    /// 数える文字列（正規表現ではなくそのまま探す、大文字小文字を区別する）
+    pub names: Vec<String>,
+}
+
+/// `check` サブコマンドで評価するルール
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckConfig {
+    pub rules: Vec<CheckRule>,
+}
+
+/// ルール（設定した上限・下限をすべて超えたノードを違反とする）
+/// 例: `max_lines = 1000` と `max_frequency = 0.5` なら「1000行を超え、かつ変更頻度が0.5を超える」ファイルが違反
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckRule {
+    /// 違反の一覧に表示する名前（省略時は条件から作る）
+    pub name: Option<String>,
+    /// 対象のファイルまたはディレクトリ（リポジトリルートからの相対パス、省略時はルート）
+    pub path: Option<PathBuf>,
+    /// trueなら対象の配下の各ファイルを、falseなら対象そのもの（ディレクトリは配下の集計値）を判定する
+    pub each_file: bool,
+    /// 行数の上限
+    pub max_lines: Option<usize>,
+    /// 変更頻度（日次）の上限
+    pub max_frequency: Option<f64>,
+    /// ホットスポットの度合い（変更頻度×行数、ディレクトリは配下の合計）の上限
+    pub max_score: Option<f64>,
+    /// 期間内のコミット数の上限
+    pub max_commits: Option<usize>,
+    /// 修正コミットの割合（%）の上限
+    pub max_fix_percent: Option<f64>,
+    /// 循環的複雑度の上限（対応していない言語のファイルは判定しない）
+    pub max_cyclomatic: Option<usize>,
+    /// TODO・FIXMEなどの目印の数の上限
+    pub max_markers: Option<usize>,
+    /// 診断結果のエラー・警告の数の上限
+    pub max_diagnostics: Option<usize>,
+    /// カバレッジ（%）の下限（レポートにないファイルは判定しない）
+    pub min_coverage: Option<f64>,
+}
+
+impl FiltersConfig {
+    /// コマンドラインで指定した条件を追加する（単一の値はコマンドラインを優先）
+    pub fn merge(&mut self, other: FiltersConfig) {
+        self.include_authors.extend(other.include_authors);
+        self.exclude_authors.extend(other.exclude_authors);
+        self.include_messages.extend(other.include_messages);
+        self.exclude_messages.extend(other.exclude_messages);
+        self.exclude_paths.extend(other.exclude_paths);
+        self.ignore_revs_file = other.ignore_revs_file.or(self.ignore_revs_file.take());
+        self.max_files = other.max_files.or(self.max_files);
+    }
+}
+
+impl Default for DefectsConfig {
+    fn default() -> Self {
+        Self {
+            patterns: vec![
+                r"^(fix|bugfix|hotfix)(\(.*\))?!?:".to_string(),
+                r"(?i)\b(fix(es|ed)?|bugs?|defect|regression)\b".to_string(),
+            ],
+        }
+    }
+}
+
+impl Default for OwnersConfig {
+    fn default() -> Self {
+        Self {
+            handles: HashMap::new(),
+            teams: HashMap::new(),
+            hot_commits: 1,
+        }
+    }
+}
+
+impl Default for MarkersConfig {
+    fn default() -> Self {
+        Self {
+            names: ["TODO", "FIXME", "HACK", "XXX", "unimplemented!", "todo!"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl Config {
+    /// 設定ファイルを読む
+    /// `path` が指定されていなければリポジトリルートの `.heatree.toml` を探し、なければデフォルト値とする
+    pub fn load(root: &Path, path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let path = root.join(CONFIG_FILE);
+                if !path.exists() {
+                    return Ok(Self::default());
+                }
+                path
+            }
+        };
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("invalid config {}", path.display()))
+    }
+}
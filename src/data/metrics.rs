@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 /// ファイル/ディレクトリのメトリクス
 #[derive(Debug, Clone, Default)]
 pub struct Metrics {
@@ -5,6 +7,182 @@ pub struct Metrics {
     pub lines: usize,
     /// 変更頻度（日次）
     pub change_frequency: f64,
+    /// 期間内のコミット数の推移（古い順、ディレクトリは配下の合計）
+    pub activity: Vec<u32>,
+    /// 解析したコミットのうちこのファイルを変更したものの数（ディレクトリは配下の合計）
+    pub commits: usize,
+    /// そのうち修正コミットの数（ディレクトリは配下の合計）
+    pub fixes: usize,
+    /// スキャン時に求めた複雑さ（Gitのツリーから構築した場合は0）
+    pub complexity: Complexity,
+    /// Gitの履歴から求めた年齢（履歴にないファイルはNone）
+    pub age: Option<CodeAge>,
+    /// カバレッジレポートから読んだ網羅行数（レポートにないファイルはNone）
+    pub coverage: Option<Coverage>,
+    /// 診断結果から読んだエラー・警告の数（ディレクトリは配下の合計）
+    pub diagnostics: Diagnostics,
+    /// TODO・FIXMEなどの目印ごとの数（ディレクトリは配下の合計）
+    pub markers: BTreeMap<String, usize>,
+    /// CODEOWNERSで宣言された所有者（ファイルのみ）
+    pub owners: Vec<String>,
+    /// 期間内に最も多くこのファイルを変更した作者（ファイルのみ）
+    pub top_committer: Option<String>,
+    /// 所有者の問題があるファイルの数（ファイルは0か1、ディレクトリは配下の合計）
+    pub ownership_issues: OwnershipIssues,
+    /// 比較モードでの比較対象時点との差分
+    pub delta: Option<MetricsDelta>,
+}
+
+/// 2時点間のメトリクスの差分（新しい時点 - 古い時点）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsDelta {
+    pub lines: i64,
+    pub change_frequency: f64,
+}
+
+/// ファイルの複雑さ（ディレクトリは配下の合計、最大インデントは最大値）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Complexity {
+    /// 行ごとのインデントの深さの合計
+    pub indent_sum: usize,
+    /// インデントの深さの最大値
+    pub indent_max: usize,
+    /// 関数の数（対応している言語のみ、ディレクトリは対応しているファイルの合計）
+    pub functions: Option<usize>,
+    /// 循環的複雑度の近似値（関数の数と同様）
+    pub cyclomatic: Option<usize>,
+    /// 最も複雑なファイルの循環的複雑度（ファイルは `cyclomatic` と同じ、ヒートマップの色に使う）
+    pub cyclomatic_max: Option<usize>,
+}
+
+impl Complexity {
+    /// 配下のファイルの複雑さを足し合わせる
+    pub fn add(&mut self, other: &Complexity) {
+        self.indent_sum += other.indent_sum;
+        self.indent_max = self.indent_max.max(other.indent_max);
+        self.functions = add_optional(self.functions, other.functions);
+        self.cyclomatic = add_optional(self.cyclomatic, other.cyclomatic);
+        self.cyclomatic_max = self.cyclomatic_max.max(other.cyclomatic_max);
+    }
+
+    /// 循環的複雑度のカテゴリを取得（ヒートマップ用、対応していない言語はNone）
+    /// ディレクトリは合計ではなく最も複雑なファイルで判定する（合計ではほとんどのディレクトリが最大のカテゴリになる）
+    pub fn cyclomatic_category(&self) -> Option<usize> {
+        self.cyclomatic_max.map(|cyclomatic| match cyclomatic {
+            0..=9 => 0,
+            10..=19 => 1,
+            20..=49 => 2,
+            50..=99 => 3,
+            _ => 4,
+        })
+    }
+}
+
+fn add_optional(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// テストで実行された行数（ディレクトリはレポートにある配下のファイルの合計）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Coverage {
+    /// 1回以上実行された行数
+    pub covered: usize,
+    /// 実行可能な行数
+    pub total: usize,
+}
+
+impl Coverage {
+    /// 網羅率（実行可能な行がなければ1.0）
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.covered as f64 / self.total as f64
+        }
+    }
+
+    /// 網羅率のカテゴリを取得（ヒートマップ用、低いほど大きい）
+    pub fn category(&self) -> usize {
+        match self.ratio() {
+            r if r < 0.5 => 3,
+            r if r < 0.7 => 2,
+            r if r < 0.85 => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// コンパイラやリンターが報告したエラー・警告の数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl Diagnostics {
+    pub fn total(&self) -> usize {
+        self.errors + self.warnings
+    }
+
+    /// エラー・警告の合計のカテゴリを取得（ヒートマップ用）
+    pub fn category(&self) -> usize {
+        match self.total() {
+            0 => 0,
+            1..=2 => 1,
+            3..=9 => 2,
+            10..=29 => 3,
+            _ => 4,
+        }
+    }
+}
+
+/// CODEOWNERSと実際の変更の食い違い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OwnershipIssues {
+    /// 期間内に変更されたのに所有者がいないファイル（回数の下限は `[owners] hot_commits`）
+    pub unowned: usize,
+    /// 最も多く変更した作者が所有者に含まれないファイル
+    pub mismatched: usize,
+}
+
+impl OwnershipIssues {
+    /// 問題のカテゴリを取得（0: なし、1: 所有者なし、2: 所有者の食い違い）
+    pub fn category(&self) -> usize {
+        if self.unowned > 0 {
+            1
+        } else if self.mismatched > 0 {
+            2
+        } else {
+            0
+        }
+    }
+}
+
+/// ファイル/ディレクトリの年齢（UNIX秒）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeAge {
+    /// 最後に変更された日時（ディレクトリは配下で最も新しいもの）
+    pub last_changed: i64,
+    /// 最初に追加された日時（ディレクトリは配下で最も古いもの）
+    pub first_seen: i64,
+    /// 配下のファイルの最終変更日時の中央値（ファイルは最終変更日時と同じ）
+    pub median_changed: i64,
+}
+
+impl CodeAge {
+    /// 経過日数のカテゴリを取得（ヒートマップ用）
+    pub fn days_category(days: i64) -> usize {
+        match days {
+            ..=6 => 0,
+            7..=29 => 1,
+            30..=89 => 2,
+            90..=364 => 3,
+            _ => 4,
+        }
+    }
 }
 
 impl Metrics {
@@ -12,6 +190,7 @@ impl Metrics {
         Self {
             lines,
             change_frequency,
+            ..Self::default()
         }
     }
 
@@ -27,6 +206,41 @@ impl Metrics {
         }
     }
 
+    /// 目印の合計数
+    pub fn marker_count(&self) -> usize {
+        self.markers.values().sum()
+    }
+
+    /// 目印の合計数のカテゴリを取得（ヒートマップ用）
+    pub fn marker_category(&self) -> usize {
+        match self.marker_count() {
+            0 => 0,
+            1..=2 => 1,
+            3..=9 => 2,
+            10..=29 => 3,
+            _ => 4,
+        }
+    }
+
+    /// 変更したコミットのうち修正コミットの割合（コミットがなければ0）
+    pub fn fix_ratio(&self) -> f64 {
+        if self.commits == 0 {
+            0.0
+        } else {
+            self.fixes as f64 / self.commits as f64
+        }
+    }
+
+    /// 修正コミットの割合のカテゴリを取得（ヒートマップ用）
+    pub fn fix_ratio_category(&self) -> usize {
+        match self.fix_ratio() {
+            ratio if ratio < 0.1 => 0,
+            ratio if ratio < 0.25 => 1,
+            ratio if ratio < 0.5 => 2,
+            _ => 3,
+        }
+    }
+
     /// 変更頻度のカテゴリを取得（ヒートマップ用）
     pub fn change_frequency_category(&self) -> usize {
         if self.change_frequency < 1.7 {
@@ -1,5 +1,7 @@
 pub mod tree;
 pub mod metrics;
+pub mod status;
 
 pub use tree::FileNode;
-pub use metrics::Metrics;
+pub use metrics::{CodeAge, Complexity, Coverage, Diagnostics, Metrics, MetricsDelta, OwnershipIssues};
+pub use status::FileStatus;
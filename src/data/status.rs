@@ -0,0 +1,24 @@
+/// 作業ツリー上のファイルの変更状態（git status）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    /// 作業ツリーで変更されている（未ステージ）
+    Modified,
+    /// 変更がステージされている
+    Staged,
+    /// Gitで追跡されていない
+    Untracked,
+    /// 作業ツリーまたはインデックスから削除されている
+    Deleted,
+}
+
+impl FileStatus {
+    /// Name列に表示するマーカー
+    pub fn marker(&self) -> &'static str {
+        match self {
+            FileStatus::Modified => "M",
+            FileStatus::Staged => "S",
+            FileStatus::Untracked => "?",
+            FileStatus::Deleted => "D",
+        }
+    }
+}
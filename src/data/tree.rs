@@ -1,16 +1,17 @@
-use std::path::PathBuf;
-use super::Metrics;
+use std::path::{Path, PathBuf};
+use super::{FileStatus, Metrics};
 
 /// ファイルツリーのノード
 #[derive(Debug, Clone)]
 pub struct FileNode {
     pub name: String,
-    #[allow(dead_code)]
     pub path: PathBuf,
     pub is_dir: bool,
     pub children: Vec<FileNode>,
     pub metrics: Metrics,
     pub is_expanded: bool,
+    /// 作業ツリーの変更状態（変更がなければNone）
+    pub status: Option<FileStatus>,
 }
 
 impl FileNode {
@@ -22,6 +23,7 @@ impl FileNode {
             children: Vec::new(),
             metrics: Metrics::default(),
             is_expanded: true,
+            status: None,
         }
     }
 
@@ -45,32 +47,162 @@ impl FileNode {
         }
     }
 
+    /// パスに一致するノードを探す
+    pub fn find_mut(&mut self, path: &Path) -> Option<&mut FileNode> {
+        if self.path == path {
+            return Some(self);
+        }
+        if !path.starts_with(&self.path) {
+            return None;
+        }
+
+        self.children
+            .iter_mut()
+            .find(|child| path.starts_with(&child.path))
+            .and_then(|child| child.find_mut(path))
+    }
+
+    /// パスに一致するノードを探す（読み取り用）
+    pub fn find(&self, path: &Path) -> Option<&FileNode> {
+        if self.path == path {
+            return Some(self);
+        }
+        if !path.starts_with(&self.path) {
+            return None;
+        }
+
+        self.children
+            .iter()
+            .find(|child| path.starts_with(&child.path))
+            .and_then(|child| child.find(path))
+    }
+
+    /// 配下のファイルを深さ優先で集める（削除済みとして表示しているだけのファイルは除く）
+    pub fn collect_files<'a>(&'a self, result: &mut Vec<&'a FileNode>) {
+        if !self.is_dir {
+            if self.status != Some(FileStatus::Deleted) {
+                result.push(self);
+            }
+            return;
+        }
+        for child in &self.children {
+            child.collect_files(result);
+        }
+    }
+
+    /// ホットスポットの度合い（ファイルは変更頻度×行数、ディレクトリは配下のファイルの合計）
+    pub fn hotspot_score(&self) -> f64 {
+        if self.is_dir {
+            self.children.iter().map(FileNode::hotspot_score).sum()
+        } else {
+            self.metrics.change_frequency * self.metrics.lines as f64
+        }
+    }
+
+    /// ノードを追加または置き換える（存在しない中間ディレクトリは作成する）
+    /// 置き換え時はディレクトリの展開状態を引き継ぐ
+    pub fn upsert(&mut self, mut node: FileNode) {
+        let Some(parent_path) = node.path.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        if !parent_path.starts_with(&self.path) {
+            return;
+        }
+
+        if self.find_mut(&parent_path).is_none() {
+            let name = parent_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            self.upsert(FileNode::new(name, parent_path.clone(), true));
+        }
+
+        let Some(parent) = self.find_mut(&parent_path) else {
+            return;
+        };
+        match parent.children.iter_mut().find(|child| child.path == node.path) {
+            Some(existing) => {
+                node.is_expanded = existing.is_expanded;
+                *existing = node;
+            }
+            None => {
+                parent.children.push(node);
+                parent.sort_children();
+            }
+        }
+    }
+
+    /// パスに一致するノードを削除
+    pub fn remove(&mut self, path: &Path) -> bool {
+        let Some(parent) = path.parent().and_then(|parent| self.find_mut(parent)) else {
+            return false;
+        };
+
+        let before = parent.children.len();
+        parent.children.retain(|child| child.path != path);
+        parent.children.len() != before
+    }
+
+    /// 作業ツリーの変更状態をすべて消す
+    pub fn clear_status(&mut self) {
+        self.status = None;
+        for child in &mut self.children {
+            child.clear_status();
+        }
+    }
+
+    /// 削除済みとして表示しているだけのノードを取り除く
+    pub fn remove_deleted(&mut self) {
+        self.children
+            .retain(|child| child.status != Some(FileStatus::Deleted));
+        for child in &mut self.children {
+            child.remove_deleted();
+        }
+    }
+
+    /// 自身または子孫に作業ツリーの変更があるか
+    pub fn has_changes(&self) -> bool {
+        self.status.is_some() || self.children.iter().any(FileNode::has_changes)
+    }
+
+    /// 自身または子孫のいずれかが条件を満たすか（フィルタ表示用）
+    pub fn matches(&self, filter: &dyn Fn(&FileNode) -> bool) -> bool {
+        filter(self) || self.children.iter().any(|child| child.matches(filter))
+    }
+
     /// ツリーを走査して表示用のアイテムリストを生成
-    /// 戻り値: (depth, node, is_last_child, parent_continues)
-    /// - is_last_child: このノードが親の最後の子かどうか
-    /// - parent_continues: 各階層で親が継続しているかのリスト
-    pub fn flatten(&self, depth: usize, result: &mut Vec<(usize, FileNode)>) {
+    /// `filter` を満たす子孫を持たない子ノードは除外する
+    pub fn flatten(&self, depth: usize, filter: &dyn Fn(&FileNode) -> bool, result: &mut Vec<(usize, FileNode)>) {
         result.push((depth, self.clone()));
 
         if self.is_expanded && self.is_dir {
-            for child in &self.children {
-                child.flatten(depth + 1, result);
+            for child in self.children.iter().filter(|child| child.matches(filter)) {
+                child.flatten(depth + 1, filter, result);
             }
         }
     }
 
     /// ツリー罫線情報付きでフラット化
-    pub fn flatten_with_lines(&self, depth: usize, is_last: bool, parent_lines: &[bool], result: &mut Vec<(usize, FileNode, bool, Vec<bool>)>) {
+    /// 戻り値: (depth, node, is_last_child, parent_continues)
+    /// - is_last_child: このノードが親の最後の子かどうか
+    /// - parent_continues: 各階層で親が継続しているかのリスト
+    pub fn flatten_with_lines(&self, depth: usize, is_last: bool, parent_lines: &[bool], filter: &dyn Fn(&FileNode) -> bool, result: &mut Vec<(usize, FileNode, bool, Vec<bool>)>) {
         result.push((depth, self.clone(), is_last, parent_lines.to_vec()));
 
         if self.is_expanded && self.is_dir {
-            let child_count = self.children.len();
+            let visible: Vec<&FileNode> = self
+                .children
+                .iter()
+                .filter(|child| child.matches(filter))
+                .collect();
+            let child_count = visible.len();
             let mut new_parent_lines = parent_lines.to_vec();
             new_parent_lines.push(!is_last);
 
-            for (i, child) in self.children.iter().enumerate() {
+            for (i, child) in visible.into_iter().enumerate() {
                 let is_last_child = i == child_count - 1;
-                child.flatten_with_lines(depth + 1, is_last_child, &new_parent_lines, result);
+                child.flatten_with_lines(depth + 1, is_last_child, &new_parent_lines, filter, result);
             }
         }
     }
@@ -1,33 +1,93 @@
 mod analyzer;
+mod check;
+mod cli;
+mod config;
 mod data;
+mod report;
 mod ui;
 
-use analyzer::{scan_directory, GitAnalyzer};
-use anyhow::Result;
+use analyzer::blame::export_blame;
+use analyzer::codeowners::CodeOwners;
+use analyzer::coverage::load_coverage;
+use analyzer::diagnostics::load_diagnostics;
+use analyzer::git::RefSpec;
+use analyzer::{GitAnalyzer, Loader};
+use anyhow::{bail, Context, Result};
+use check::{run_checks, Violation};
+use cli::{Command, Options};
+use report::{render_report, touched_files};
 use crossterm::{
     event::{self, Event, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::env;
+use std::fs;
 use std::io;
-use std::path::PathBuf;
-use ui::{render::render, App};
+use ui::{app::Column, render::render, App};
 
-fn main() -> Result<()> {
-    // コマンドライン引数からパスを取得（デフォルトはカレントディレクトリ）
-    let path = env::args()
-        .nth(1)
-        .map(PathBuf::from)
-        .unwrap_or_else(|| env::current_dir().unwrap());
+/// `check` でルールに違反したときの終了コード
+const EXIT_VIOLATIONS: i32 = 1;
+/// 引数・設定の誤りや解析の失敗で終了するときの終了コード（`check` の違反と区別する）
+const EXIT_ERROR: i32 = 2;
 
-    // Git解析
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {:?}", err);
+        std::process::exit(EXIT_ERROR);
+    }
+}
+
+fn run() -> Result<()> {
+    // コマンドライン引数を解析（パスのデフォルトはカレントディレクトリ）
+    let options = Options::parse()?;
+    let path = options.path.clone();
+
+    // blameの書き出しはTUIを起動せずに実行する（--rev でリビジョンを指定できる）
+    if let Some(output) = &options.export_blame {
+        let count = export_blame(&path, options.rev.as_deref(), output)?;
+        println!("Wrote blame of {} files to {}", count, output.display());
+        return Ok(());
+    }
+
+    // リポジトリを開けない場合はTUIを起動する前にエラーにする
     let analyzer = GitAnalyzer::new(&path)?;
-    let frequency_map = analyzer.analyze_change_frequency(30)?; // 過去30日分
 
-    // ディレクトリスキャン
-    let root = scan_directory(&path, &frequency_map)?;
+    // Git解析とディレクトリスキャン（比較モードでは2時点の解析）はバックグラウンドで実行
+    let loader = Loader::spawn(analyzer, &options)?;
+    let mut app = create_app(&options)?;
+
+    // checkはTUIを起動せずに解析の完了を待ってルールを評価する
+    if options.command == Command::Check {
+        if options.config.check.rules.is_empty() {
+            bail!("no check rules configured (add [[check.rules]] to {})", config::CONFIG_FILE);
+        }
+        wait_for_analysis(&mut app, &loader)?;
+        let violations = run_checks(&app.root, &options.config.check.rules)?;
+        print_violations(&violations, options.config.check.rules.len());
+        if !violations.is_empty() {
+            std::process::exit(EXIT_VIOLATIONS);
+        }
+        return Ok(());
+    }
+
+    // reportも解析の完了を待ち、範囲指定があればその範囲で変更されたファイルも載せる
+    if options.command == Command::Report {
+        wait_for_analysis(&mut app, &loader)?;
+        let touched = match &options.refs {
+            RefSpec::Range(range) => Some(touched_files(&GitAnalyzer::new(&path)?, &options, range)?),
+            _ => None,
+        };
+        let report = render_report(&app.root, &options, touched.as_ref());
+        match &options.output {
+            Some(output) => {
+                fs::write(output, report).with_context(|| format!("failed to write {}", output.display()))?;
+                println!("Wrote report to {}", output.display());
+            }
+            None => print!("{}", report),
+        }
+        return Ok(());
+    }
 
     // TUIセットアップ
     enable_raw_mode()?;
@@ -37,8 +97,7 @@ fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // アプリケーション実行
-    let mut app = App::new(root);
-    let res = run_app(&mut terminal, &mut app);
+    let res = run_app(&mut terminal, &mut app, &loader);
 
     // TUIクリーンアップ
     disable_raw_mode()?;
@@ -52,17 +111,86 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// 表示の設定と、起動時に読み込むカバレッジ・診断結果・CODEOWNERSを反映したアプリケーションを作る
+fn create_app(options: &Options) -> Result<App> {
+    let path = &options.path;
+    let mut app = App::new(path.clone());
+    app.compare_label = options.compare.as_ref().map(|spec| spec.label());
+    app.revision = options.rev.clone();
+    app.history_label = (options.refs != RefSpec::Head).then(|| options.refs.label());
+    app.half_life = options.half_life;
+    app.days = options.days;
+    // カバレッジレポートや診断結果を指定した場合は最初からその列を表示する
+    if let Some(report) = &options.coverage {
+        app.coverage = load_coverage(report, path)?;
+        app.column = Some(Column::Coverage);
+    } else if !options.diagnostics.is_empty() {
+        app.column = Some(Column::Diagnostics);
+    }
+    app.diagnostics = load_diagnostics(&options.diagnostics, path)?;
+    app.codeowners = CodeOwners::load(path, &options.config.owners)?;
+    Ok(app)
+}
+
+/// バックグラウンド解析がすべて終わるまで結果を取り込む（TUIを起動しないサブコマンド用）
+fn wait_for_analysis(app: &mut App, loader: &Loader) -> Result<()> {
+    while app.status.is_loading() {
+        let event = loader.events.recv().context("analysis stopped unexpectedly")?;
+        app.handle_load_event(event);
+    }
+    if let Some(error) = &app.status.error {
+        bail!("analysis failed: {}", error);
+    }
+    Ok(())
+}
+
+/// checkの結果を表示する
+fn print_violations(violations: &[Violation], rules: usize) {
+    if violations.is_empty() {
+        println!("heatree check: all {} rules passed", rules);
+        return;
+    }
+
+    println!("heatree check: {} violations", violations.len());
+    for violation in violations {
+        println!(
+            "  [{}] {}: {}",
+            violation.rule,
+            violation.path.display(),
+            violation.reasons.join(", ")
+        );
+    }
+}
+
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
+    loader: &Loader,
 ) -> Result<()> {
     loop {
-        let items = app.get_flat_tree_with_lines();
-        let selected_index = app.selected_index;
-        terminal.draw(|f| render(f, &items, selected_index))?;
+        // バックグラウンド解析の結果を取り込む
+        while let Ok(load_event) = loader.events.try_recv() {
+            app.handle_load_event(load_event);
+        }
+        app.status.scanned_files = loader.scan_progress.files();
+
+        terminal.draw(|f| render(f, app))?;
 
         if event::poll(std::time::Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
+                // 作者の絞り込みパネルを開いている間はパネルを操作する
+                if app.author_panel.is_some() {
+                    match key.code {
+                        KeyCode::Char('a') | KeyCode::Esc => app.toggle_author_panel(),
+                        KeyCode::Char('j') | KeyCode::Down => app.move_author_cursor(true),
+                        KeyCode::Char('k') | KeyCode::Up => app.move_author_cursor(false),
+                        KeyCode::Enter | KeyCode::Char(' ') => app.toggle_author(),
+                        KeyCode::Char('x') => app.clear_authors(),
+                        _ => {}
+                    }
+                    continue;
+                }
+
                 match key.code {
                     KeyCode::Char('q') | KeyCode::Esc => {
                         app.quit();
@@ -76,11 +204,46 @@ fn run_app<B: ratatui::backend::Backend>(
                     KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Char('o') => {
                         app.toggle_selected();
                     }
+                    KeyCode::Char('d') => {
+                        app.toggle_dirty_only();
+                    }
+                    KeyCode::Char('a') => {
+                        app.toggle_author_panel();
+                    }
+                    KeyCode::Char('i') => {
+                        app.toggle_detail();
+                    }
+                    KeyCode::Char('c') => {
+                        app.cycle_column();
+                    }
+                    KeyCode::Char('w') => {
+                        app.cycle_owner_filter();
+                    }
+                    KeyCode::Char('t') => {
+                        if let Some(offset) = app.toggle_time_travel() {
+                            loader.travel(offset);
+                        }
+                    }
+                    KeyCode::Char('h') | KeyCode::Left => {
+                        if let Some(offset) = app.travel_back() {
+                            loader.travel(offset);
+                        }
+                    }
+                    KeyCode::Char('l') | KeyCode::Right => {
+                        if let Some(offset) = app.travel_forward() {
+                            loader.travel(offset);
+                        }
+                    }
                     _ => {}
                 }
             }
         }
 
+        // 詳細パネルで選択中のファイルのblameと関数ごとの変更回数がまだなければ要求する
+        if let Some(request) = app.detail_request() {
+            loader.detail(request);
+        }
+
         if app.should_quit {
             break;
         }
@@ -0,0 +1,214 @@
+use crate::analyzer::git::{HistoryQuery, RefSpec};
+use crate::analyzer::loader::history_query;
+use crate::analyzer::GitAnalyzer;
+use crate::cli::Options;
+use crate::data::FileNode;
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// 変更頻度のカテゴリ（TUIのヒートマップと同じ5段階）ごとの絵文字
+const HEAT_EMOJI: [&str; 5] = ["⬜", "🟩", "🟨", "🟧", "🟥"];
+
+/// 棒グラフの1文字未満の部分（1/8刻み）
+const PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// 棒グラフの最大の長さ（文字数）
+const BAR_WIDTH: usize = 10;
+
+/// 範囲内のコミットが変更したファイル（リポジトリルートからの相対パス）
+/// 作者・メッセージ・ignore-revsの絞り込みやマージの扱いは履歴のメトリクスと同じ条件を使い、日数とコミット数では制限しない
+pub fn touched_files(analyzer: &GitAnalyzer, options: &Options, range: &str) -> Result<BTreeSet<PathBuf>> {
+    let query = HistoryQuery {
+        refs: RefSpec::Range(range.to_string()),
+        ..history_query(analyzer, options)?.full()
+    };
+    let records = analyzer.collect_commits(&query, |_, _, _| {})?;
+    Ok(records.into_iter().flat_map(|record| record.files).collect())
+}
+
+/// TUIと同じツリーから、ホットスポット・行数が増えたファイル・範囲内で変更されたファイルをMarkdownにまとめる
+/// 行数が増えたファイルは比較モード（`--compare` または `report --range`）の差分がある場合だけ載せる
+pub fn render_report(root: &FileNode, options: &Options, touched: Option<&BTreeSet<PathBuf>>) -> String {
+    let mut files = Vec::new();
+    root.collect_files(&mut files);
+
+    let mut report = String::new();
+    let _ = writeln!(report, "## heatree report: `{}`", root.name);
+    let _ = writeln!(report);
+    let mut summary = format!("Change frequency over the last {} days", options.days);
+    if let Some(half_life) = options.half_life {
+        let _ = write!(summary, " (half-life {} days)", half_life);
+    }
+    if let Some(compare) = &options.compare {
+        let _ = write!(summary, ", compared across `{}`", compare.label());
+    } else if let Some(rev) = &options.rev {
+        let _ = write!(summary, " at `{}`", rev);
+    }
+    let _ = writeln!(report, "{}.", summary);
+
+    write_hotspots(&mut report, &root.path, &files, options.top);
+    write_growers(&mut report, &root.path, &files, options.top);
+    if let (Some(touched), RefSpec::Range(range)) = (touched, &options.refs) {
+        write_touched(&mut report, &root.path, &files, touched, range, options.top);
+    }
+
+    report
+}
+
+/// ホットスポットの度合いが高い順の表
+fn write_hotspots(report: &mut String, root: &Path, files: &[&FileNode], top: usize) {
+    let mut hotspots: Vec<&FileNode> = files
+        .iter()
+        .copied()
+        .filter(|file| file.hotspot_score() > 0.0)
+        .collect();
+    hotspots.sort_by(|a, b| b.hotspot_score().total_cmp(&a.hotspot_score()));
+    hotspots.truncate(top);
+
+    let _ = writeln!(report);
+    let _ = writeln!(report, "### Top {} hotspots", top);
+    let _ = writeln!(report);
+    if hotspots.is_empty() {
+        let _ = writeln!(report, "_No files changed in this period._");
+        return;
+    }
+
+    let max = hotspots.first().map_or(0.0, |file| file.hotspot_score());
+    let _ = writeln!(report, "| | File | Score | Lines | Changes/day | Commits |");
+    let _ = writeln!(report, "|---|---|---|---:|---:|---:|");
+    for file in hotspots {
+        let score = file.hotspot_score();
+        let _ = writeln!(
+            report,
+            "| {} | {} | {}{:.1} | {} | {:.2} | {} |",
+            heat_emoji(file),
+            file_cell(file, root),
+            heat_bar(score, max),
+            score,
+            file.metrics.lines,
+            file.metrics.change_frequency,
+            file.metrics.commits
+        );
+    }
+}
+
+/// 比較対象の時点から行数が多く増えた順の表
+fn write_growers(report: &mut String, root: &Path, files: &[&FileNode], top: usize) {
+    if files.iter().all(|file| file.metrics.delta.is_none()) {
+        return;
+    }
+
+    let mut growers: Vec<(&FileNode, i64)> = files
+        .iter()
+        .filter_map(|file| Some((*file, file.metrics.delta?.lines)))
+        .filter(|(_, growth)| *growth > 0)
+        .collect();
+    growers.sort_by_key(|(_, growth)| std::cmp::Reverse(*growth));
+    growers.truncate(top);
+
+    let _ = writeln!(report);
+    let _ = writeln!(report, "### Biggest growers");
+    let _ = writeln!(report);
+    if growers.is_empty() {
+        let _ = writeln!(report, "_No file grew._");
+        return;
+    }
+
+    let max = growers.first().map_or(0.0, |(_, growth)| *growth as f64);
+    let _ = writeln!(report, "| | File | Growth | Lines | Changes/day |");
+    let _ = writeln!(report, "|---|---|---|---:|---:|");
+    for (file, growth) in growers {
+        let _ = writeln!(
+            report,
+            "| {} | {} | {}+{} | {} | {:.2} |",
+            heat_emoji(file),
+            file_cell(file, root),
+            heat_bar(growth as f64, max),
+            growth,
+            file.metrics.lines,
+            file.metrics.change_frequency
+        );
+    }
+}
+
+/// 範囲内で変更されたファイルをホットスポットの度合いが高い順に並べた表
+/// 範囲の後に削除されたなどツリーにないファイルは数だけに含める
+fn write_touched(
+    report: &mut String,
+    root: &Path,
+    files: &[&FileNode],
+    touched: &BTreeSet<PathBuf>,
+    range: &str,
+    top: usize,
+) {
+    let mut nodes: Vec<&FileNode> = files
+        .iter()
+        .copied()
+        .filter(|file| touched.contains(relative_path(&file.path, root)))
+        .collect();
+    nodes.sort_by(|a, b| b.hotspot_score().total_cmp(&a.hotspot_score()));
+
+    let _ = writeln!(report);
+    let _ = writeln!(report, "### Files touched by `{}` ({})", range, touched.len());
+    let _ = writeln!(report);
+    if nodes.is_empty() {
+        let _ = writeln!(report, "_No files in the tree were touched._");
+        return;
+    }
+
+    let max = files.iter().map(|file| file.hotspot_score()).fold(0.0, f64::max);
+    let _ = writeln!(report, "| | File | Score | Lines | Δ Lines | Changes/day |");
+    let _ = writeln!(report, "|---|---|---|---:|---:|---:|");
+    for file in nodes.iter().take(top) {
+        let score = file.hotspot_score();
+        let delta = file
+            .metrics
+            .delta
+            .map_or_else(|| "-".to_string(), |delta| format!("{:+}", delta.lines));
+        let _ = writeln!(
+            report,
+            "| {} | {} | {}{:.1} | {} | {} | {:.2} |",
+            heat_emoji(file),
+            file_cell(file, root),
+            heat_bar(score, max),
+            score,
+            file.metrics.lines,
+            delta,
+            file.metrics.change_frequency
+        );
+    }
+    if nodes.len() > top {
+        let _ = writeln!(report);
+        let _ = writeln!(report, "_…and {} more._", nodes.len() - top);
+    }
+}
+
+/// 変更頻度のカテゴリの絵文字
+fn heat_emoji(file: &FileNode) -> &'static str {
+    HEAT_EMOJI[file.metrics.change_frequency_category().min(HEAT_EMOJI.len() - 1)]
+}
+
+/// `max` を最大の長さとした棒グラフのコード（1/8文字刻み、0より大きい値は最低1/8文字、0なら空）
+fn heat_bar(value: f64, max: f64) -> String {
+    if max <= 0.0 || value <= 0.0 {
+        return String::new();
+    }
+    let eighths = ((value / max * (BAR_WIDTH * 8) as f64).round() as usize).clamp(1, BAR_WIDTH * 8);
+    let mut bar = "█".repeat(eighths / 8);
+    if let Some(partial) = (eighths % 8).checked_sub(1) {
+        bar.push(PARTIAL_BLOCKS[partial]);
+    }
+    format!("`{}` ", bar)
+}
+
+/// 表のファイル名のセル（Markdownの表を壊す `|` はエスケープする）
+fn file_cell(file: &FileNode, root: &Path) -> String {
+    let name = relative_path(&file.path, root).to_string_lossy().replace('`', "'").replace('|', "\\|");
+    format!("`{}`", name)
+}
+
+fn relative_path<'a>(path: &'a Path, root: &Path) -> &'a Path {
+    path.strip_prefix(root).unwrap_or(path)
+}
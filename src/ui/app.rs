@@ -1,17 +1,555 @@
-use crate::data::FileNode;
+use crate::analyzer::blame::BlameSummary;
+use crate::analyzer::codeowners::{apply_owners, CodeOwners};
+use crate::analyzer::detail::DetailRequest;
+use crate::analyzer::functions::FunctionHotspots;
+use crate::analyzer::git::CommitRecord;
+use crate::analyzer::history::HistoryMetrics;
+use crate::analyzer::{apply_ages, apply_coverage, apply_diagnostics, apply_history, LoadEvent};
+use crate::data::{CodeAge, Coverage, Diagnostics, FileNode, FileStatus};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// バックグラウンド解析の進行状況
+#[derive(Debug, Default)]
+pub struct LoadStatus {
+    /// スキャン済みのファイル数（ツリーが届くまで表示に使う）
+    pub scanned_files: usize,
+    pub tree_loaded: bool,
+    /// 解析済みコミット数
+    pub commits_done: usize,
+    /// 解析対象のコミット数
+    pub commits_total: usize,
+    pub git_finished: bool,
+    pub error: Option<String>,
+}
+
+impl LoadStatus {
+    pub fn is_loading(&self) -> bool {
+        self.error.is_none() && !(self.tree_loaded && self.git_finished)
+    }
+}
+
+/// LINES・CHANGES・ACTIVITYに加えて表示する指標の列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// 最終変更・最初の追加からの経過日数
+    Age,
+    /// 修正コミットの数と割合
+    Defects,
+    /// インデントの深さと関数の数・循環的複雑度
+    Complexity,
+    /// カバレッジレポートの網羅行数と網羅率
+    Coverage,
+    /// コンパイラ・リンターのエラーと警告の数
+    Diagnostics,
+    /// CODEOWNERSの所有者と所有者の問題
+    Owners,
+    /// TODO・FIXMEなどの目印の数
+    Markers,
+}
+
+impl Column {
+    /// 切り替える順序
+    const ALL: [Column; 7] = [
+        Column::Age,
+        Column::Defects,
+        Column::Complexity,
+        Column::Coverage,
+        Column::Diagnostics,
+        Column::Owners,
+        Column::Markers,
+    ];
+}
+
+/// 所有者によるツリーの絞り込み
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OwnerFilter {
+    /// CODEOWNERSでこの所有者が宣言されたファイル
+    Owner(String),
+    /// 所有者のいないファイル
+    Unowned,
+}
+
+/// 作者の絞り込みパネルの状態
+pub struct AuthorPanel {
+    /// カーソル位置（`App::authors` の順）
+    pub cursor: usize,
+}
+
+/// タイムトラベル（過去の期間の表示）の状態
+pub struct TimeTravel {
+    /// 表示している期間が何期間前か（0は直近の期間）
+    pub offset: usize,
+    /// 要求した期間のツリーがまだ届いていない
+    pub loading: bool,
+    /// 作業ツリーのツリー（タイムトラベル中に届いた更新はこちらに反映する）
+    live_root: FileNode,
+}
 
 pub struct App {
     pub root: FileNode,
     pub should_quit: bool,
     pub selected_index: usize,
+    pub status: LoadStatus,
+    /// 作業ツリーに変更のあるファイルだけを表示する
+    pub dirty_only: bool,
+    /// 比較モードの比較内容（通常モードではNone）
+    pub compare_label: Option<String>,
+    /// 作業ツリーの代わりに表示しているリビジョン
+    pub revision: Option<String>,
+    /// HEAD以外の履歴を解析している場合の走査対象
+    pub history_label: Option<String>,
+    /// 変更頻度を指数減衰で重み付けしている場合の半減期（日数）
+    pub half_life: Option<f64>,
+    /// 変更頻度を計算する期間（タイムトラベルでは1期間の日数）
+    pub days: usize,
+    /// タイムトラベル中の状態（通常表示ではNone）
+    pub travel: Option<TimeTravel>,
+    /// 追加で表示している指標の列
+    pub column: Option<Column>,
+    /// カバレッジレポートから読んだ網羅行数（リポジトリルートからの相対パス）
+    pub coverage: HashMap<PathBuf, Coverage>,
+    /// 診断結果から読んだエラー・警告の数（リポジトリルートからの相対パス）
+    pub diagnostics: HashMap<PathBuf, Diagnostics>,
+    /// リポジトリのCODEOWNERS（ない場合はNone）
+    pub codeowners: Option<CodeOwners>,
+    /// 所有者による絞り込み（Noneならすべて表示）
+    pub owner_filter: Option<OwnerFilter>,
+    /// 選択中のファイルの詳細パネルを表示する
+    pub detail: bool,
+    /// 作者の絞り込みパネル（開いている間はキー操作がパネルに向く）
+    pub author_panel: Option<AuthorPanel>,
+    /// 変更を表示する作者（空ならすべての作者）
+    pub selected_authors: BTreeSet<String>,
+    /// これまでに受け取った履歴メトリクス（ツリーより先に届いた場合もここで保持する）
+    history: HistoryMetrics,
+    /// 履歴メトリクスを求めたコミット（詳細パネルの関数ごとの変更回数で数える）
+    records: Arc<Vec<CommitRecord>>,
+    /// 全履歴から求めた各ファイルの年齢
+    ages: HashMap<PathBuf, CodeAge>,
+    /// 詳細パネルで表示したファイルのblame結果
+    blame: HashMap<PathBuf, Result<BlameSummary, String>>,
+    /// 詳細パネルで表示したファイルの関数ごとの変更回数
+    functions: HashMap<PathBuf, Result<FunctionHotspots, String>>,
+    /// 最後に詳細を要求したファイル
+    detail_requested: Option<PathBuf>,
+    /// 作業ツリーの変更状態（リポジトリルートからの相対パス）
+    status_map: HashMap<PathBuf, FileStatus>,
 }
 
 impl App {
-    pub fn new(root: FileNode) -> Self {
+    /// ルートのみのツリーで開始し、解析結果は `handle_load_event` で順次反映する
+    pub fn new(root_path: PathBuf) -> Self {
+        let name = root_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+
         Self {
-            root,
+            root: FileNode::new(name, root_path, true),
             should_quit: false,
             selected_index: 0,
+            status: LoadStatus::default(),
+            dirty_only: false,
+            compare_label: None,
+            revision: None,
+            history_label: None,
+            half_life: None,
+            days: 30,
+            travel: None,
+            column: None,
+            coverage: HashMap::new(),
+            diagnostics: HashMap::new(),
+            codeowners: None,
+            owner_filter: None,
+            detail: false,
+            author_panel: None,
+            selected_authors: BTreeSet::new(),
+            history: HistoryMetrics::default(),
+            records: Arc::default(),
+            ages: HashMap::new(),
+            blame: HashMap::new(),
+            functions: HashMap::new(),
+            detail_requested: None,
+            status_map: HashMap::new(),
+        }
+    }
+
+    /// バックグラウンド解析のイベントを反映
+    /// タイムトラベル中は表示中の期間を保ったまま、作業ツリーのツリーを更新する
+    pub fn handle_load_event(&mut self, event: LoadEvent) {
+        if matches!(event, LoadEvent::Travel { .. } | LoadEvent::Error(_)) || self.travel.is_none() {
+            self.apply_load_event(event);
+            return;
+        }
+
+        let selected_index = self.selected_index;
+        self.swap_live_root();
+        self.apply_load_event(event);
+        self.swap_live_root();
+        self.selected_index = selected_index;
+    }
+
+    fn apply_load_event(&mut self, event: LoadEvent) {
+        match event {
+            LoadEvent::Tree(root) => {
+                self.root = root;
+                self.status.tree_loaded = true;
+                self.refresh_status();
+                self.refresh_history();
+            }
+            LoadEvent::Status(status_map) => {
+                self.status_map = status_map;
+                self.with_selection_preserved(|app| {
+                    app.refresh_status();
+                    app.refresh_history();
+                });
+            }
+            LoadEvent::History { metrics, records, done, total } => {
+                self.history = metrics;
+                self.records = records;
+                self.status.commits_done = done;
+                self.status.commits_total = total;
+                self.refresh_history();
+            }
+            LoadEvent::Ages(ages) => {
+                // 新しいコミットで行の作者や年齢、関数の変更回数も変わり得る
+                self.blame.clear();
+                self.functions.clear();
+                self.detail_requested = None;
+                self.ages = ages;
+                self.refresh_history();
+            }
+            LoadEvent::GitFinished => {
+                self.status.git_finished = true;
+                // 解析の途中に求めた関数ごとの変更回数はすべてのコミットで求め直す
+                self.functions.clear();
+                self.detail_requested = None;
+            }
+            LoadEvent::Travel { offset, tree } => {
+                self.apply_travel_tree(offset, tree);
+            }
+            LoadEvent::Blame(path, result) => {
+                self.blame.insert(path, result);
+            }
+            LoadEvent::Functions(path, result) => {
+                self.functions.insert(path, result);
+            }
+            LoadEvent::PathChanged(path, node) => {
+                self.apply_path_change(&path, node);
+            }
+            LoadEvent::Error(message) => {
+                self.status.error = Some(message);
+            }
+        }
+    }
+
+    /// 監視で検知した変更をツリーに反映（展開状態と選択中のノードは維持する）
+    fn apply_path_change(&mut self, path: &Path, node: Option<FileNode>) {
+        match node {
+            // 既存のディレクトリは配下のイベントで個別に更新されるため、置き換えない
+            Some(node) if node.is_dir && self.root.find_mut(path).is_some() => {}
+            Some(node) => self.with_selection_preserved(|app| {
+                app.root.upsert(node);
+                app.refresh_status();
+                app.refresh_history();
+            }),
+            None => self.with_selection_preserved(|app| {
+                if app.root.remove(path) {
+                    app.refresh_history();
+                }
+            }),
+        }
+    }
+
+    /// ツリー構造を変える処理の前後で選択中のノードを維持する
+    fn with_selection_preserved(&mut self, update: impl FnOnce(&mut Self)) {
+        let selected_path = self.selected_path();
+        update(self);
+        if let Some(selected_path) = selected_path {
+            self.select_path(&selected_path);
+        }
+    }
+
+    /// 作業ツリーの変更状態をツリーに反映
+    /// 削除されたファイルはディスク上に存在しないため、表示用のノードを追加する
+    fn refresh_status(&mut self) {
+        if !self.status.tree_loaded {
+            return;
+        }
+
+        self.root.remove_deleted();
+        self.root.clear_status();
+
+        let root_path = self.root.path.clone();
+        for (relative_path, status) in &self.status_map {
+            // スキャン時と同様に隠しファイル/ディレクトリは対象外
+            if relative_path
+                .components()
+                .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+            {
+                continue;
+            }
+
+            let path = root_path.join(relative_path);
+            if *status == FileStatus::Deleted {
+                let name = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                let mut node = FileNode::new(name, path.clone(), false);
+                node.status = Some(FileStatus::Deleted);
+                self.root.upsert(node);
+            } else if let Some(node) = self.root.find_mut(&path) {
+                node.status = Some(*status);
+            }
+        }
+    }
+
+    /// タイムトラベルを開始/終了する
+    /// 開始した場合は要求すべき期間（何期間前か）を返す。比較モードとリビジョン表示では使えない
+    pub fn toggle_time_travel(&mut self) -> Option<usize> {
+        if let Some(travel) = self.travel.take() {
+            self.with_selection_preserved(|app| {
+                let previous = std::mem::replace(&mut app.root, travel.live_root);
+                copy_expansion(&previous, &mut app.root);
+            });
+            return None;
+        }
+        if self.compare_label.is_some() || self.revision.is_some() {
+            return None;
+        }
+
+        // 最初の期間が届くまでは作業ツリーのツリーを表示しておく
+        self.travel = Some(TimeTravel {
+            offset: 0,
+            loading: true,
+            live_root: self.root.clone(),
+        });
+        Some(0)
+    }
+
+    /// 1期間前へ移動し、要求すべき期間を返す
+    pub fn travel_back(&mut self) -> Option<usize> {
+        let travel = self.travel.as_mut()?;
+        travel.offset += 1;
+        travel.loading = true;
+        Some(travel.offset)
+    }
+
+    /// 1期間後へ移動し、要求すべき期間を返す（直近の期間より先へは進まない）
+    pub fn travel_forward(&mut self) -> Option<usize> {
+        let travel = self.travel.as_mut().filter(|travel| travel.offset > 0)?;
+        travel.offset -= 1;
+        travel.loading = true;
+        Some(travel.offset)
+    }
+
+    /// 届いた期間のツリーを表示する（古い要求の結果は捨てる）
+    fn apply_travel_tree(&mut self, offset: usize, mut tree: FileNode) {
+        match &mut self.travel {
+            Some(travel) if travel.offset == offset => travel.loading = false,
+            _ => return,
+        }
+        self.with_selection_preserved(|app| {
+            copy_expansion(&app.root, &mut tree);
+            app.root = tree;
+        });
+    }
+
+    fn swap_live_root(&mut self) {
+        if let Some(travel) = &mut self.travel {
+            std::mem::swap(&mut self.root, &mut travel.live_root);
+        }
+    }
+
+    /// 詳細パネルの表示を切り替える
+    pub fn toggle_detail(&mut self) {
+        self.detail = !self.detail;
+    }
+
+    /// 詳細パネルに表示するためにblameと関数ごとの変更回数が必要なファイルを返す（同じファイルは一度だけ要求する）
+    /// blameが取得済みなら関数ごとの変更回数だけを要求する
+    pub fn detail_request(&mut self) -> Option<DetailRequest> {
+        if !self.detail {
+            return None;
+        }
+        let node = self.selected_node()?;
+        let blame = !self.blame.contains_key(&node.path);
+        if node.is_dir
+            || node.status == Some(FileStatus::Deleted)
+            || (!blame && self.functions.contains_key(&node.path))
+            || self.detail_requested.as_ref() == Some(&node.path)
+        {
+            return None;
+        }
+        self.detail_requested = Some(node.path.clone());
+        Some(DetailRequest {
+            path: node.path,
+            blame,
+            records: Arc::clone(&self.records),
+        })
+    }
+
+    /// ファイルのblame結果（未取得の場合はNone）
+    pub fn blame_of(&self, path: &Path) -> Option<&Result<BlameSummary, String>> {
+        self.blame.get(path)
+    }
+
+    /// ファイルの関数ごとの変更回数（未取得の場合はNone）
+    pub fn functions_of(&self, path: &Path) -> Option<&Result<FunctionHotspots, String>> {
+        self.functions.get(path)
+    }
+
+    /// 選択中のノード
+    pub fn selected_node(&self) -> Option<FileNode> {
+        self.get_flat_tree()
+            .into_iter()
+            .nth(self.selected_index)
+            .map(|(_, node)| node)
+    }
+
+    /// 解析した期間にコミットした作者とコミット数（多い順）
+    pub fn authors(&self) -> Vec<(String, usize)> {
+        let mut authors: Vec<(String, usize)> = self
+            .history
+            .authors
+            .iter()
+            .map(|(author, metrics)| (author.clone(), metrics.total_commits))
+            .collect();
+        authors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        authors
+    }
+
+    /// 作者の絞り込みパネルを開く/閉じる
+    /// 比較モードでは差分の基準と揃わず、タイムトラベル中は表示中のツリーが作業ツリーではないため使えない
+    pub fn toggle_author_panel(&mut self) {
+        if self.author_panel.take().is_none() && self.compare_label.is_none() && self.travel.is_none() {
+            self.author_panel = Some(AuthorPanel { cursor: 0 });
+        }
+    }
+
+    /// パネルのカーソルを移動
+    pub fn move_author_cursor(&mut self, down: bool) {
+        let count = self.authors().len();
+        if let Some(panel) = &mut self.author_panel {
+            panel.cursor = if down {
+                (panel.cursor + 1).min(count.saturating_sub(1))
+            } else {
+                panel.cursor.saturating_sub(1)
+            };
+        }
+    }
+
+    /// カーソル位置の作者の選択を切り替え、ツリーを色付けし直す
+    pub fn toggle_author(&mut self) {
+        let Some(panel) = &self.author_panel else {
+            return;
+        };
+        let Some((author, _)) = self.authors().into_iter().nth(panel.cursor) else {
+            return;
+        };
+        if !self.selected_authors.remove(&author) {
+            self.selected_authors.insert(author);
+        }
+        self.with_selection_preserved(|app| app.refresh_history());
+    }
+
+    /// 作者の選択をすべて解除する
+    pub fn clear_authors(&mut self) {
+        self.selected_authors.clear();
+        self.with_selection_preserved(|app| app.refresh_history());
+    }
+
+    /// 追加の列を順に切り替える（最後の次は非表示）
+    pub fn cycle_column(&mut self) {
+        let next = match self.column {
+            None => 0,
+            Some(column) => Column::ALL.iter().position(|&c| c == column).unwrap_or(0) + 1,
+        };
+        self.column = Column::ALL.get(next).copied();
+    }
+
+    /// 所有者による絞り込みを順に切り替える（各所有者、所有者なし、絞り込みなしの順）
+    /// CODEOWNERSがない場合は何もしない
+    pub fn cycle_owner_filter(&mut self) {
+        let Some(codeowners) = &self.codeowners else {
+            return;
+        };
+        let filters: Vec<OwnerFilter> = codeowners
+            .all_owners()
+            .into_iter()
+            .map(OwnerFilter::Owner)
+            .chain([OwnerFilter::Unowned])
+            .collect();
+        let next = match &self.owner_filter {
+            None => filters.first().cloned(),
+            Some(current) => filters
+                .iter()
+                .position(|filter| filter == current)
+                .and_then(|index| filters.get(index + 1))
+                .cloned(),
+        };
+        self.with_selection_preserved(|app| app.owner_filter = next);
+    }
+
+    /// 作業ツリーの変更があるファイルのみの表示を切り替える
+    pub fn toggle_dirty_only(&mut self) {
+        self.with_selection_preserved(|app| app.dirty_only = !app.dirty_only);
+    }
+
+    /// 表示フィルタ（子孫のいずれかが満たせば親ディレクトリも表示される）
+    fn is_visible(&self, node: &FileNode) -> bool {
+        let owned = match &self.owner_filter {
+            None => true,
+            Some(_) if node.is_dir => false,
+            Some(OwnerFilter::Owner(owner)) => node.metrics.owners.contains(owner),
+            Some(OwnerFilter::Unowned) => node.metrics.owners.is_empty(),
+        };
+        (!self.dirty_only || node.status.is_some()) && owned
+    }
+
+    /// 選択中のノードのパス
+    fn selected_path(&self) -> Option<PathBuf> {
+        self.get_flat_tree()
+            .get(self.selected_index)
+            .map(|(_, node)| node.path.clone())
+    }
+
+    /// パスに一致するノードを選択（見つからない場合は範囲内に収める）
+    fn select_path(&mut self, path: &Path) {
+        let items = self.get_flat_tree();
+        match items.iter().position(|(_, node)| node.path == path) {
+            Some(index) => self.selected_index = index,
+            None => self.selected_index = self.selected_index.min(items.len().saturating_sub(1)),
+        }
+    }
+
+    fn refresh_history(&mut self) {
+        if !self.status.tree_loaded {
+            return;
+        }
+        let root_path = self.root.path.clone();
+        if self.selected_authors.is_empty() {
+            apply_history(&mut self.root, &root_path, &self.history);
+        } else {
+            // 選択した作者のコミットだけから求めたメトリクスで色付けする
+            let mut history = HistoryMetrics::default();
+            for author in &self.selected_authors {
+                if let Some(metrics) = self.history.authors.get(author) {
+                    history.merge(metrics);
+                }
+            }
+            apply_history(&mut self.root, &root_path, &history);
+        }
+        apply_ages(&mut self.root, &root_path, &self.ages);
+        apply_coverage(&mut self.root, &root_path, &self.coverage);
+        apply_diagnostics(&mut self.root, &root_path, &self.diagnostics);
+        if let Some(codeowners) = &self.codeowners {
+            apply_owners(&mut self.root, &root_path, codeowners, &self.history);
         }
     }
 
@@ -22,14 +560,14 @@ impl App {
     /// ツリーを平坦化して表示用のリストを取得
     pub fn get_flat_tree(&self) -> Vec<(usize, FileNode)> {
         let mut result = Vec::new();
-        self.root.flatten(0, &mut result);
+        self.root.flatten(0, &|node| self.is_visible(node), &mut result);
         result
     }
 
     /// ツリー罫線情報付きで平坦化
     pub fn get_flat_tree_with_lines(&self) -> Vec<(usize, FileNode, bool, Vec<bool>)> {
         let mut result = Vec::new();
-        self.root.flatten_with_lines(0, true, &[], &mut result);
+        self.root.flatten_with_lines(0, true, &[], &|node| self.is_visible(node), &mut result);
         result
     }
 
@@ -81,3 +619,16 @@ impl App {
         false
     }
 }
+
+/// ツリーを差し替える際に、同じパスのディレクトリの展開状態を引き継ぐ
+fn copy_expansion(from: &FileNode, to: &mut FileNode) {
+    if !from.is_dir {
+        return;
+    }
+    if let Some(node) = to.find_mut(&from.path) {
+        node.is_expanded = from.is_expanded;
+    }
+    for child in &from.children {
+        copy_expansion(child, to);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::data::FileStatus;
 use ratatui::style::Color;
 
 /// 行数カテゴリに対応する色を取得
@@ -22,3 +23,97 @@ pub fn get_change_frequency_color(category: usize) -> Color {
         _ => Color::Rgb(220, 50, 50),    // 6.9+: レッド
     }
 }
+
+/// 経過日数カテゴリに対応する色を取得（新しいほど明るく、古いほどくすんだ色）
+pub fn get_age_color(category: usize) -> Color {
+    match category {
+        0 => Color::Rgb(120, 230, 150),  // <1週間: ライトグリーン
+        1 => Color::Rgb(50, 180, 180),   // 1週間-1ヶ月: ティール
+        2 => Color::Rgb(50, 120, 200),   // 1-3ヶ月: ブルー
+        3 => Color::Rgb(120, 80, 180),   // 3ヶ月-1年: パープル
+        _ => Color::Rgb(110, 100, 90),   // 1年以上: ブラウングレー
+    }
+}
+
+/// 修正コミットの割合カテゴリに対応する色を取得
+pub fn get_fix_ratio_color(category: usize) -> Color {
+    match category {
+        0 => Color::Rgb(100, 100, 100),  // <10%: グレー
+        1 => Color::Rgb(200, 200, 100),  // 10-25%: イエロー
+        2 => Color::Rgb(255, 165, 0),    // 25-50%: オレンジ
+        _ => Color::Rgb(220, 50, 50),    // 50%+: レッド
+    }
+}
+
+/// 循環的複雑度カテゴリに対応する色を取得
+pub fn get_complexity_color(category: usize) -> Color {
+    match category {
+        0 => Color::Rgb(100, 150, 200),  // <10: ブルー
+        1 => Color::Rgb(100, 200, 100),  // 10-20: グリーン
+        2 => Color::Rgb(200, 200, 100),  // 20-50: イエロー
+        3 => Color::Rgb(255, 165, 0),    // 50-100: オレンジ
+        _ => Color::Rgb(220, 50, 50),    // 100+: レッド
+    }
+}
+
+/// 網羅率カテゴリに対応する色を取得（網羅率が低いほど赤い）
+pub fn get_coverage_color(category: usize) -> Color {
+    match category {
+        0 => Color::Rgb(100, 200, 100),  // 85%+: グリーン
+        1 => Color::Rgb(200, 200, 100),  // 70-85%: イエロー
+        2 => Color::Rgb(255, 165, 0),    // 50-70%: オレンジ
+        _ => Color::Rgb(220, 50, 50),    // <50%: レッド
+    }
+}
+
+/// エラー・警告の数のカテゴリに対応する色を取得
+pub fn get_diagnostics_color(category: usize) -> Color {
+    match category {
+        0 => Color::Rgb(100, 100, 100),  // 0: グレー
+        1 => Color::Rgb(100, 150, 200),  // 1-2: ブルー
+        2 => Color::Rgb(200, 200, 100),  // 3-9: イエロー
+        3 => Color::Rgb(255, 165, 0),    // 10-29: オレンジ
+        _ => Color::Rgb(220, 50, 50),    // 30+: レッド
+    }
+}
+
+/// 所有者の問題のカテゴリに対応する色を取得
+pub fn get_ownership_color(category: usize) -> Color {
+    match category {
+        0 => Color::Rgb(150, 150, 150),  // 問題なし: グレー
+        1 => Color::Rgb(220, 50, 50),    // 所有者なし: レッド
+        _ => Color::Rgb(255, 165, 0),    // 所有者の食い違い: オレンジ
+    }
+}
+
+/// 目印の数のカテゴリに対応する色を取得
+pub fn get_marker_color(category: usize) -> Color {
+    match category {
+        0 => Color::Rgb(100, 100, 100),  // 0: グレー
+        1 => Color::Rgb(150, 130, 200),  // 1-2: ラベンダー
+        2 => Color::Rgb(180, 100, 200),  // 3-9: パープル
+        3 => Color::Rgb(220, 80, 160),   // 10-29: マゼンタ
+        _ => Color::Rgb(220, 50, 50),    // 30+: レッド
+    }
+}
+
+/// 作業ツリーの変更状態に対応する色を取得
+pub fn get_status_color(status: FileStatus) -> Color {
+    match status {
+        FileStatus::Modified => Color::Rgb(255, 200, 50),  // 未ステージの変更: イエロー
+        FileStatus::Staged => Color::Rgb(100, 200, 100),   // ステージ済み: グリーン
+        FileStatus::Untracked => Color::Rgb(50, 150, 200), // 未追跡: シアン
+        FileStatus::Deleted => Color::Rgb(220, 50, 50),    // 削除: レッド
+    }
+}
+
+/// 比較モードの差分に対応する色を取得（増加は赤、減少は緑）
+pub fn get_delta_color(delta: f64) -> Color {
+    if delta > 0.0 {
+        Color::Rgb(220, 50, 50)
+    } else if delta < 0.0 {
+        Color::Rgb(100, 200, 100)
+    } else {
+        Color::Rgb(100, 100, 100)
+    }
+}
@@ -1,30 +1,290 @@
-use crate::data::FileNode;
-use crate::ui::colors::{get_lines_color, get_change_frequency_color};
+use crate::analyzer::history::{unix_now, SECONDS_PER_DAY};
+use crate::data::{CodeAge, FileNode, FileStatus};
+use crate::ui::app::{App, Column, LoadStatus, OwnerFilter};
+use crate::ui::colors::{
+    get_age_color, get_change_frequency_color, get_complexity_color, get_coverage_color, get_diagnostics_color, get_marker_color, get_ownership_color, get_delta_color, get_fix_ratio_color, get_lines_color,
+    get_status_color,
+};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Table, Row, Cell},
+    widgets::{Block, Borders, Clear, LineGauge, Paragraph, Table, Row, Cell},
     Frame,
 };
 
-pub fn render(frame: &mut Frame, items: &[(usize, FileNode, bool, Vec<bool>)], selected_index: usize) {
+pub fn render(frame: &mut Frame, app: &App) {
+    // 解析中・エラー時のみステータス行を表示
+    let status_height = if app.status.is_loading() || app.status.error.is_some() {
+        1
+    } else {
+        0
+    };
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(5), // ヘッダー（レジェンド）- 2行分に拡大
-            Constraint::Min(0),    // メインコンテンツ
+            Constraint::Length(5),             // ヘッダー（レジェンド）- 2行分に拡大
+            Constraint::Min(0),                // メインコンテンツ
+            Constraint::Length(status_height), // ステータス（進捗バー）
         ])
         .split(frame.area());
 
-    render_legend(frame, chunks[0]);
-    render_tree(frame, chunks[1], items, selected_index);
+    let items = app.get_flat_tree_with_lines();
+
+    render_legend(frame, chunks[0], app);
+    if app.detail {
+        let main = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(DETAIL_HEIGHT)])
+            .split(chunks[1]);
+        render_tree(frame, main[0], &items, app);
+        render_detail(frame, main[1], app);
+    } else {
+        render_tree(frame, chunks[1], &items, app);
+    }
+    if let Some(panel) = &app.author_panel {
+        render_author_panel(frame, chunks[1], app, panel.cursor);
+    }
+    render_status(frame, chunks[2], &app.status);
+}
+
+/// 作者の絞り込みパネルの幅
+const AUTHOR_PANEL_WIDTH: u16 = 50;
+
+/// 作者の一覧をツリーの上に重ねて表示
+fn render_author_panel(frame: &mut Frame, area: Rect, app: &App, cursor: usize) {
+    // 枠と1行を描けないほど端末が小さい場合は表示しない
+    if area.height < 3 || area.width < 3 {
+        return;
+    }
+
+    let authors = app.authors();
+    let width = AUTHOR_PANEL_WIDTH.min(area.width);
+    let rows = u16::try_from(authors.len()).unwrap_or(u16::MAX).saturating_add(2);
+    let height = rows.max(3).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    // カーソルが見える位置までスクロール
+    let visible = height.saturating_sub(2) as usize;
+    let offset = (cursor + 1).saturating_sub(visible);
+
+    let name_width = width.saturating_sub(16) as usize;
+    let lines: Vec<Line> = if authors.is_empty() {
+        vec![Line::from(Span::styled("No commits in the analysis window.", Style::default().fg(Color::Gray)))]
+    } else {
+        authors
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(visible)
+            .map(|(index, (author, commits))| {
+                let mark = if app.selected_authors.contains(author) { "[x]" } else { "[ ]" };
+                let name: String = author.chars().take(name_width).collect();
+                let style = if index == cursor {
+                    Style::default().bg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                Line::from(Span::styled(
+                    format!("{} {:<name_width$} {:>6}", mark, name, commits),
+                    style,
+                ))
+            })
+            .collect()
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("authors (space: select, x: clear, a: close)");
+    frame.render_widget(Clear, popup);
+    frame.render_widget(Paragraph::new(lines).block(block), popup);
+}
+
+/// 詳細パネルの高さ（枠、メトリクス、見出し、年齢の5カテゴリ分）
+const DETAIL_HEIGHT: u16 = 9;
+
+/// 詳細パネルの割合バーの最大幅
+const DETAIL_BAR_WIDTH: usize = 16;
+
+/// 選択中のノードの詳細（ファイルの場合はblameによる行の作者と年齢の分布）を表示
+fn render_detail(frame: &mut Frame, area: Rect, app: &App) {
+    let Some(node) = app.selected_node() else {
+        frame.render_widget(Block::default().borders(Borders::ALL), area);
+        return;
+    };
+
+    let title = node
+        .path
+        .strip_prefix(&app.root.path)
+        .unwrap_or(&node.path)
+        .to_string_lossy()
+        .to_string();
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+
+    let label_style = Style::default().fg(Color::Gray);
+    let mut summary = vec![
+        Span::styled("Lines: ", label_style),
+        Span::raw(node.metrics.lines.to_string()),
+        Span::styled("  Changes/day: ", label_style),
+        Span::raw(format!("{:.1}", node.metrics.change_frequency)),
+    ];
+    // CODEOWNERSがある場合は所有者と、最も多く変更した作者が所有者かどうか
+    if app.codeowners.is_some() && !node.is_dir {
+        let issues = node.metrics.ownership_issues;
+        let owners = match node.metrics.owners.is_empty() {
+            true => "(unowned)".to_string(),
+            false => node.metrics.owners.join(" "),
+        };
+        summary.push(Span::styled("  Owners: ", label_style));
+        summary.push(Span::styled(owners, Style::default().fg(get_ownership_color(issues.category()))));
+        if let Some(author) = &node.metrics.top_committer {
+            summary.push(Span::styled("  Top committer: ", label_style));
+            summary.push(Span::raw(author.clone()));
+            if issues.mismatched > 0 {
+                summary.push(Span::styled(" (not an owner)", Style::default().fg(get_ownership_color(2))));
+            }
+        }
+    }
+    frame.render_widget(Paragraph::new(Line::from(summary)), rows[0]);
+
+    // 左から作者ごとの行数、行の年齢の分布、関数ごとの変更回数を並べる
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(34), Constraint::Percentage(30), Constraint::Percentage(36)])
+        .split(rows[1]);
+
+    if node.is_dir {
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled("Select a file to run blame.", label_style))),
+            rows[1],
+        );
+        return;
+    }
+
+    match app.blame_of(&node.path) {
+        None => frame.render_widget(Paragraph::new(Span::styled("Running blame...", label_style)), columns[0]),
+        Some(Err(err)) => frame.render_widget(
+            Paragraph::new(Span::styled(format!("blame failed: {}", err), Style::default().fg(Color::Red))),
+            columns[0],
+        ),
+        Some(Ok(blame)) => {
+            let rev = app.revision.as_deref().unwrap_or("HEAD");
+            let mut owners = vec![Line::from(Span::styled(
+                format!("Line ownership ({} lines at {}):", blame.lines, rev),
+                label_style,
+            ))];
+            let visible_owners = columns[0].height.saturating_sub(1) as usize;
+            for owner in blame.owners.iter().take(visible_owners) {
+                owners.push(create_share_line(&owner.author, owner.lines, blame.lines, Color::Rgb(50, 150, 200)));
+            }
+            frame.render_widget(Paragraph::new(owners), columns[0]);
+
+            let mut ages = vec![Line::from(Span::styled("Line age:", label_style))];
+            let labels = ["<1w", "1w-1mo", "1-3mo", "3mo-1y", "1y+"];
+            for (category, (label, count)) in labels.iter().zip(blame.age_distribution).enumerate() {
+                ages.push(create_share_line(label, count, blame.lines, get_age_color(category)));
+            }
+            frame.render_widget(Paragraph::new(ages), columns[1]);
+        }
+    }
+
+    // 関数ごとの変更回数（割合はファイルを変更したコミットに対するもの）
+    let functions = match app.functions_of(&node.path) {
+        None => vec![Line::from(Span::styled("Mapping changes to functions...", label_style))],
+        Some(Err(err)) => vec![Line::from(Span::styled(
+            format!("function history failed: {}", err),
+            Style::default().fg(Color::Red),
+        ))],
+        Some(Ok(hotspots)) => {
+            let mut lines = vec![Line::from(Span::styled(
+                format!("Changes by function ({} commits in {} days):", hotspots.commits, app.days),
+                label_style,
+            ))];
+            if hotspots.functions.is_empty() {
+                lines.push(Line::from(Span::styled("  No changes in the analysis window.", label_style)));
+            }
+            let visible = columns[2].height.saturating_sub(1) as usize;
+            for function in hotspots.functions.iter().take(visible) {
+                lines.push(create_share_line(
+                    &function.name,
+                    function.changes,
+                    hotspots.commits,
+                    Color::Rgb(255, 165, 0),
+                ));
+            }
+            lines
+        }
+    };
+    frame.render_widget(Paragraph::new(functions), columns[2]);
+}
+
+/// 詳細パネルの「名前 割合 バー」の行を作成
+fn create_share_line(name: &str, count: usize, total: usize, color: Color) -> Line<'static> {
+    let share = if total > 0 { count as f64 / total as f64 } else { 0.0 };
+    let name: String = name.chars().take(18).collect();
+    Line::from(vec![
+        Span::raw(format!("  {:<18} {:>4.0}% ", name, share * 100.0)),
+        Span::styled(
+            "█".repeat((share * DETAIL_BAR_WIDTH as f64).round() as usize),
+            Style::default().fg(color),
+        ),
+    ])
+}
+
+/// 解析の進捗バーを表示
+fn render_status(frame: &mut Frame, area: Rect, status: &LoadStatus) {
+    if area.height == 0 {
+        return;
+    }
+
+    if let Some(error) = &status.error {
+        frame.render_widget(
+            Paragraph::new(error.clone()).style(Style::default().fg(Color::Red)),
+            area,
+        );
+        return;
+    }
+
+    let (label, ratio) = if !status.tree_loaded {
+        (format!("Scanning... {} files", status.scanned_files), 0.0)
+    } else if status.commits_total > 0 {
+        (
+            format!(
+                "Analyzing git history {}/{} (q to quit)",
+                status.commits_done, status.commits_total
+            ),
+            status.commits_done as f64 / status.commits_total as f64,
+        )
+    } else {
+        ("Analyzing git history...".to_string(), 0.0)
+    };
+
+    let gauge = LineGauge::default()
+        .label(label)
+        .ratio(ratio.clamp(0.0, 1.0))
+        .filled_style(Style::default().fg(Color::Rgb(50, 150, 200)))
+        .unfilled_style(Style::default().fg(Color::DarkGray));
+
+    frame.render_widget(gauge, area);
 }
 
-fn render_legend(frame: &mut Frame, area: Rect) {
+fn render_legend(frame: &mut Frame, area: Rect, app: &App) {
     let legend_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(2), Constraint::Length(2)])
+        .constraints([Constraint::Length(2), Constraint::Length(2), Constraint::Length(1)])
         .margin(0)
         .split(area);
 
@@ -39,12 +299,16 @@ fn render_legend(frame: &mut Frame, area: Rect) {
             (4, "500-1K"),
             (5, "1K+"),
         ],
-        true,
+        get_lines_color,
     );
 
-    // Change Frequency レジェンド
+    // Change Frequency レジェンド（減衰モードでは半減期を表示）
+    let freq_label = match app.half_life {
+        Some(half_life) => format!("Weighted Changes/day (half-life {}d):", half_life),
+        None => "Change Frequency/day:".to_string(),
+    };
     let freq_legend = create_legend_line(
-        "Change Frequency/day:",
+        &freq_label,
         &[
             (0, "<1.7"),
             (1, "1.7-3.4"),
@@ -52,7 +316,7 @@ fn render_legend(frame: &mut Frame, area: Rect) {
             (3, "5.2-6.9"),
             (4, "6.9+"),
         ],
-        false,
+        get_change_frequency_color,
     );
 
     frame.render_widget(
@@ -68,22 +332,66 @@ fn render_legend(frame: &mut Frame, area: Rect) {
             .style(Style::default().bg(Color::Black)),
         legend_chunks[1],
     );
+
+    // 追加の列を表示している場合はその凡例
+    let column_legend = match app.column {
+        Some(Column::Age) => create_legend_line(
+            "Last Changed (median for dirs):",
+            &[
+                (0, "<1w"),
+                (1, "1w-1mo"),
+                (2, "1-3mo"),
+                (3, "3mo-1y"),
+                (4, "1y+"),
+            ],
+            get_age_color,
+        ),
+        Some(Column::Defects) => create_legend_line(
+            "Fix Commit Ratio:",
+            &[(0, "<10%"), (1, "10-25%"), (2, "25-50%"), (3, "50%+")],
+            get_fix_ratio_color,
+        ),
+        Some(Column::Complexity) => create_legend_line(
+            "Cyclomatic Complexity (dirs: most complex file):",
+            &[(0, "<10"), (1, "10-20"), (2, "20-50"), (3, "50-100"), (4, "100+")],
+            get_complexity_color,
+        ),
+        Some(Column::Coverage) => create_legend_line(
+            "Line Coverage:",
+            &[(0, "85%+"), (1, "70-85%"), (2, "50-70%"), (3, "<50%")],
+            get_coverage_color,
+        ),
+        Some(Column::Diagnostics) => create_legend_line(
+            "Errors + Warnings:",
+            &[(0, "0"), (1, "1-2"), (2, "3-9"), (3, "10-29"), (4, "30+")],
+            get_diagnostics_color,
+        ),
+        Some(Column::Owners) => create_legend_line(
+            "Owners:",
+            &[(0, "declared"), (1, "hot but unowned"), (2, "top committer not an owner")],
+            get_ownership_color,
+        ),
+        Some(Column::Markers) => create_legend_line(
+            "TODO/FIXME Markers:",
+            &[(0, "0"), (1, "1-2"), (2, "3-9"), (3, "10-29"), (4, "30+")],
+            get_marker_color,
+        ),
+        None => return,
+    };
+    frame.render_widget(
+        Paragraph::new(column_legend).style(Style::default().bg(Color::Black)),
+        legend_chunks[2],
+    );
 }
 
-fn create_legend_line(label: &str, items: &[(usize, &str)], is_lines: bool) -> Line<'static> {
+fn create_legend_line(label: &str, items: &[(usize, &str)], color: fn(usize) -> Color) -> Line<'static> {
     let mut spans = vec![Span::styled(
         format!("{}  ", label),
         Style::default().fg(Color::White),
     )];
 
     for (category, text) in items {
-        let color = if is_lines {
-            get_lines_color(*category)
-        } else {
-            get_change_frequency_color(*category)
-        };
-
-        spans.push(Span::styled("■ ", Style::default().fg(color)));
+        spans.push(Span::styled("■ ", Style::default().fg(color(*category))));
         spans.push(Span::styled(
             format!("{}  ", text),
             Style::default().fg(Color::Gray),
@@ -93,41 +401,126 @@ fn create_legend_line(label: &str, items: &[(usize, &str)], is_lines: bool) -> L
     Line::from(spans)
 }
 
-fn render_tree(frame: &mut Frame, area: Rect, items: &[(usize, FileNode, bool, Vec<bool>)], selected_index: usize) {
+fn render_tree(frame: &mut Frame, area: Rect, items: &[(usize, FileNode, bool, Vec<bool>)], app: &App) {
+    let show_delta = app.compare_label.is_some();
+    let header_style = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
+
     // ヘッダー行を作成
-    let header = Row::new(vec![
+    let mut header_cells = vec![
         Cell::from(""),
-        Cell::from(format!("{:>20} ", "LINES")).style(Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
-        Cell::from(format!("{:>20} ", "CHANGES")).style(Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
-    ]);
+        Cell::from(format!("{:>20} ", "LINES")).style(header_style),
+        Cell::from(format!("{:>20} ", "CHANGES")).style(header_style),
+        Cell::from(format!(" {}", "ACTIVITY")).style(header_style),
+    ];
+    match app.column {
+        Some(Column::Age) => header_cells.push(
+            Cell::from(format!("{:>7}{:>8}{:>8} ", "LAST", "MEDIAN", "FIRST")).style(header_style),
+        ),
+        Some(Column::Defects) => header_cells.push(
+            Cell::from(format!("{:>7}{:>8} ", "FIXES", "RATIO")).style(header_style),
+        ),
+        Some(Column::Complexity) => header_cells.push(
+            Cell::from(format!("{:>8}{:>6}{:>6}{:>7} ", "INDENT", "MAX", "FN", "CC")).style(header_style),
+        ),
+        Some(Column::Coverage) => header_cells.push(
+            Cell::from(format!("{:>8}{:>8}{:>7} ", "COVERED", "TOTAL", "PCT")).style(header_style),
+        ),
+        Some(Column::Diagnostics) => header_cells.push(
+            Cell::from(format!("{:>8}{:>10} ", "ERRORS", "WARNINGS")).style(header_style),
+        ),
+        Some(Column::Owners) => header_cells.push(Cell::from(format!(" {}", "OWNERS")).style(header_style)),
+        Some(Column::Markers) => header_cells.push(
+            Cell::from(format!("{:>8}  {}", "MARKERS", "BREAKDOWN")).style(header_style),
+        ),
+        None => {}
+    }
+    if show_delta {
+        header_cells.push(Cell::from(format!("{:>11} ", "ΔLINES")).style(header_style));
+        header_cells.push(Cell::from(format!("{:>11} ", "ΔCHANGES")).style(header_style));
+    }
+    let header = Row::new(header_cells);
 
     // データ行を作成
     let mut rows = Vec::new();
     for (index, (depth, node, is_last, parent_lines)) in items.iter().skip(1).enumerate() {
         let actual_index = index + 1;
-        let is_selected = actual_index == selected_index;
+        let is_selected = actual_index == app.selected_index;
 
-        let row = create_table_row(*depth, node, *is_last, parent_lines, is_selected);
+        let row = create_table_row(*depth, node, *is_last, parent_lines, is_selected, show_delta, app.column);
         rows.push(row);
     }
 
     // 列幅の設定
-    let widths = [
+    let mut widths = vec![
         Constraint::Percentage(50),  // Name列（可変）
         Constraint::Length(40),       // LINES列（固定40文字）
         Constraint::Length(40),       // CHANGES列（固定40文字）
+        Constraint::Length(ACTIVITY_WIDTH as u16 + 2), // ACTIVITY列
     ];
+    match app.column {
+        Some(Column::Age) => widths.push(Constraint::Length(24)), // AGE列
+        Some(Column::Defects) => widths.push(Constraint::Length(16)), // DEFECTS列
+        Some(Column::Complexity) => widths.push(Constraint::Length(28)), // COMPLEXITY列
+        Some(Column::Coverage) => widths.push(Constraint::Length(24)), // COVERAGE列
+        Some(Column::Diagnostics) => widths.push(Constraint::Length(19)), // DIAGNOSTICS列
+        Some(Column::Owners) => widths.push(Constraint::Length(OWNERS_WIDTH as u16 + 2)), // OWNERS列
+        Some(Column::Markers) => widths.push(Constraint::Length(MARKERS_WIDTH as u16 + 11)), // MARKERS列
+        None => {}
+    }
+    if show_delta {
+        widths.push(Constraint::Length(12)); // ΔLINES列
+        widths.push(Constraint::Length(12)); // ΔCHANGES列
+    }
+
+    let mut title = String::from("project-root");
+    if let Some(revision) = &app.revision {
+        title.push_str(&format!(" [rev: {}]", revision));
+    }
+    if let Some(label) = &app.history_label {
+        title.push_str(&format!(" [history: {}]", label));
+    }
+    if let Some(label) = &app.compare_label {
+        title.push_str(&format!(" [compare: {}]", label));
+    }
+    if !app.selected_authors.is_empty() {
+        let authors: Vec<&str> = app.selected_authors.iter().map(String::as_str).collect();
+        title.push_str(&format!(" [authors: {}]", authors.join(", ")));
+    }
+    if let Some(travel) = &app.travel {
+        // 期間は現在から遡った日数で表す
+        let newest = travel.offset * app.days;
+        let period = match newest {
+            0 => format!("last {} days", app.days),
+            _ => format!("{}-{} days ago", newest, newest + app.days),
+        };
+        let loading = if travel.loading { " loading..." } else { "" };
+        title.push_str(&format!(" [time travel: {}{}]", period, loading));
+    }
+    match &app.owner_filter {
+        Some(OwnerFilter::Owner(owner)) => title.push_str(&format!(" [owner: {}]", owner)),
+        Some(OwnerFilter::Unowned) => title.push_str(" [unowned]"),
+        None => {}
+    }
+    if app.dirty_only {
+        title.push_str(" [dirty only]");
+    }
 
     let table = Table::new(rows, widths)
         .header(header)
-        .block(Block::default().borders(Borders::ALL).title("project-root"))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .column_spacing(0); // スペースなし（各セル内でパディングを制御）
 
     frame.render_widget(table, area);
 }
 
+/// ACTIVITY列（スパークライン）の最大文字数
+const ACTIVITY_WIDTH: usize = 24;
+
+/// スパークラインの文字（低い順）
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
 /// Tableの行を作成
-fn create_table_row(depth: usize, node: &FileNode, is_last: bool, parent_lines: &[bool], is_selected: bool) -> Row<'static> {
+fn create_table_row(depth: usize, node: &FileNode, is_last: bool, parent_lines: &[bool], is_selected: bool, show_delta: bool, column: Option<Column>) -> Row<'static> {
     // Name列の内容を作成
     let name_cell = create_name_cell(depth, node, is_last, parent_lines, is_selected);
 
@@ -143,7 +536,34 @@ fn create_table_row(depth: usize, node: &FileNode, is_last: bool, parent_lines:
         Style::default()
     };
 
-    Row::new(vec![name_cell, lines_cell, changes_cell])
+    // ACTIVITY列の内容を作成
+    let activity_cell = create_activity_cell(node);
+
+    let mut cells = vec![name_cell, lines_cell, changes_cell, activity_cell];
+
+    // 追加の列
+    match column {
+        Some(Column::Age) => cells.push(create_age_cell(node)),
+        Some(Column::Defects) => cells.push(create_defects_cell(node)),
+        Some(Column::Complexity) => cells.push(create_complexity_cell(node)),
+        Some(Column::Coverage) => cells.push(create_coverage_cell(node)),
+        Some(Column::Diagnostics) => cells.push(create_diagnostics_cell(node)),
+        Some(Column::Owners) => cells.push(create_owners_cell(node)),
+        Some(Column::Markers) => cells.push(create_markers_cell(node)),
+        None => {}
+    }
+
+    // 比較モードでは差分列を追加
+    if show_delta {
+        let delta = node.metrics.delta.unwrap_or_default();
+        cells.push(create_delta_cell(delta.lines as f64, format!("{:+}", delta.lines)));
+        cells.push(create_delta_cell(
+            delta.change_frequency,
+            format!("{:+.1}", delta.change_frequency),
+        ));
+    }
+
+    Row::new(cells)
         .style(style)
         .height(1)
 }
@@ -190,9 +610,39 @@ fn create_name_cell(depth: usize, node: &FileNode, is_last: bool, parent_lines:
         node.name.clone()
     };
 
-    let content = format!("{}{}{}", tree_lines, icon, display_name);
+    let mut spans = vec![Span::raw(format!("{}{}", tree_lines, icon))];
+
+    // 作業ツリーの変更マーカー（ディレクトリは配下に変更がある場合のみ）
+    if let Some(status) = node.status {
+        spans.push(Span::styled(
+            format!("{} ", status.marker()),
+            Style::default().fg(get_status_color(status)).add_modifier(Modifier::BOLD),
+        ));
+    } else if node.is_dir && node.has_changes() {
+        spans.push(Span::styled("● ", Style::default().fg(Color::Rgb(200, 200, 100))));
+    }
 
-    Cell::from(content).style(Style::default().fg(Color::White))
+    let name_style = if node.status == Some(FileStatus::Deleted) {
+        Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    spans.push(Span::styled(display_name, name_style));
+
+    Cell::from(Line::from(spans)).style(Style::default().fg(Color::White))
+}
+
+/// 差分列のセルを作成（増加は▲、減少は▼）
+fn create_delta_cell(delta: f64, value_str: String) -> Cell<'static> {
+    let arrow = if delta > 0.0 {
+        "▲"
+    } else if delta < 0.0 {
+        "▼"
+    } else {
+        " "
+    };
+
+    Cell::from(format!("{:>9} {} ", value_str, arrow)).style(Style::default().fg(get_delta_color(delta)))
 }
 
 /// LINES列のセルを作成（グラフバー付き）
@@ -325,4 +775,182 @@ fn create_changes_cell(node: &FileNode, _is_selected: bool) -> Cell<'static> {
     Cell::from(line)
 }
 
+/// ACTIVITY列のセルを作成（期間内のコミット数の推移をスパークラインで表示）
+fn create_activity_cell(node: &FileNode) -> Cell<'static> {
+    let activity = &node.metrics.activity;
+    if activity.iter().all(|&count| count == 0) {
+        return Cell::from("");
+    }
+
+    // 列幅に収まらない場合は隣り合うバケットをまとめる
+    let group = activity.len().div_ceil(ACTIVITY_WIDTH);
+    let buckets: Vec<u32> = activity.chunks(group).map(|chunk| chunk.iter().sum()).collect();
+
+    // ノードごとの最大値で正規化し、コミットのないバケットは空白にする
+    let max = buckets.iter().copied().max().unwrap_or(0).max(1);
+    let spark: String = buckets
+        .iter()
+        .map(|&count| match count {
+            0 => ' ',
+            count => SPARK_LEVELS[(count as usize * SPARK_LEVELS.len() - 1) / max as usize],
+        })
+        .collect();
+
+    let color = get_change_frequency_color(node.metrics.change_frequency_category());
+    Cell::from(Line::from(vec![
+        Span::raw(" "),
+        Span::styled(spark, Style::default().fg(color)),
+    ]))
+}
 
+/// AGE列のセルを作成（最終変更・中央値・最初の追加からの経過期間、色は中央値）
+fn create_age_cell(node: &FileNode) -> Cell<'static> {
+    let Some(age) = node.metrics.age else {
+        return Cell::from("");
+    };
+
+    let now = unix_now();
+    let days = |time: i64| (now - time).max(0) / SECONDS_PER_DAY;
+    let color = get_age_color(CodeAge::days_category(days(age.median_changed)));
+
+    Cell::from(Line::from(Span::styled(
+        format!(
+            "{:>7}{:>8}{:>8} ",
+            format_days(days(age.last_changed)),
+            format_days(days(age.median_changed)),
+            format_days(days(age.first_seen)),
+        ),
+        Style::default().fg(color),
+    )))
+}
+
+/// DEFECTS列のセルを作成（修正コミットの数と、変更したコミットに占める割合）
+fn create_defects_cell(node: &FileNode) -> Cell<'static> {
+    if node.metrics.commits == 0 {
+        return Cell::from("");
+    }
+
+    let color = get_fix_ratio_color(node.metrics.fix_ratio_category());
+    Cell::from(Line::from(Span::styled(
+        format!("{:>7}{:>7.0}% ", node.metrics.fixes, node.metrics.fix_ratio() * 100.0),
+        Style::default().fg(color),
+    )))
+}
+
+/// COMPLEXITY列のセルを作成（インデントの合計・最大と、対応している言語の関数の数・循環的複雑度）
+fn create_complexity_cell(node: &FileNode) -> Cell<'static> {
+    let complexity = &node.metrics.complexity;
+    if node.metrics.lines == 0 {
+        return Cell::from("");
+    }
+
+    let optional = |value: Option<usize>| value.map_or_else(|| "-".to_string(), |value| value.to_string());
+    let color = complexity
+        .cyclomatic_category()
+        .map_or(Color::Rgb(100, 100, 100), get_complexity_color);
+    Cell::from(Line::from(Span::styled(
+        format!(
+            "{:>8}{:>6}{:>6}{:>7} ",
+            complexity.indent_sum,
+            complexity.indent_max,
+            optional(complexity.functions),
+            optional(complexity.cyclomatic)
+        ),
+        Style::default().fg(color),
+    )))
+}
+
+/// COVERAGE列のセルを作成（網羅行数・実行可能な行数・網羅率、レポートにないファイルは空）
+fn create_coverage_cell(node: &FileNode) -> Cell<'static> {
+    let Some(coverage) = node.metrics.coverage else {
+        return Cell::from("");
+    };
+
+    Cell::from(Line::from(Span::styled(
+        format!(
+            "{:>8}{:>8}{:>6.0}% ",
+            coverage.covered,
+            coverage.total,
+            coverage.ratio() * 100.0
+        ),
+        Style::default().fg(get_coverage_color(coverage.category())),
+    )))
+}
+
+/// DIAGNOSTICS列のセルを作成（エラーと警告の数、どちらもなければ空）
+fn create_diagnostics_cell(node: &FileNode) -> Cell<'static> {
+    let diagnostics = node.metrics.diagnostics;
+    if diagnostics.total() == 0 {
+        return Cell::from("");
+    }
+
+    Cell::from(Line::from(Span::styled(
+        format!("{:>8}{:>10} ", diagnostics.errors, diagnostics.warnings),
+        Style::default().fg(get_diagnostics_color(diagnostics.category())),
+    )))
+}
+
+/// OWNERS列の表示幅
+const OWNERS_WIDTH: usize = 30;
+
+/// OWNERS列のセルを作成（ファイルは所有者、ディレクトリは所有者の問題があるファイルの数）
+fn create_owners_cell(node: &FileNode) -> Cell<'static> {
+    let issues = node.metrics.ownership_issues;
+    let text = if node.is_dir {
+        match (issues.unowned, issues.mismatched) {
+            (0, 0) => String::new(),
+            (unowned, 0) => format!("{} unowned", unowned),
+            (0, mismatched) => format!("{} mismatched", mismatched),
+            (unowned, mismatched) => format!("{} unowned, {} mismatched", unowned, mismatched),
+        }
+    } else if node.metrics.owners.is_empty() {
+        "(unowned)".to_string()
+    } else {
+        node.metrics.owners.join(" ")
+    };
+
+    let text: String = text.chars().take(OWNERS_WIDTH).collect();
+    Cell::from(Line::from(Span::styled(
+        format!(" {}", text),
+        Style::default().fg(get_ownership_color(issues.category())),
+    )))
+}
+
+/// MARKERS列の内訳の表示幅
+const MARKERS_WIDTH: usize = 24;
+
+/// MARKERS列のセルを作成（目印の合計と、多い順の内訳）
+fn create_markers_cell(node: &FileNode) -> Cell<'static> {
+    let total = node.metrics.marker_count();
+    if total == 0 {
+        return Cell::from("");
+    }
+
+    let mut markers: Vec<(&String, &usize)> = node.metrics.markers.iter().collect();
+    markers.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let breakdown: String = markers
+        .iter()
+        .map(|(marker, count)| format!("{} {}", marker, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+        .chars()
+        .take(MARKERS_WIDTH)
+        .collect();
+
+    Cell::from(Line::from(vec![
+        Span::styled(
+            format!("{:>8}  ", total),
+            Style::default().fg(get_marker_color(node.metrics.marker_category())),
+        ),
+        Span::styled(breakdown, Style::default().fg(Color::Gray)),
+    ]))
+}
+
+/// 経過日数を短く表示（100日未満は日、2年未満は月、それ以上は年）
+fn format_days(days: i64) -> String {
+    match days {
+        ..=99 => format!("{}d", days),
+        100..=729 => format!("{}mo", days / 30),
+        _ => format!("{:.1}y", days as f64 / 365.0),
+    }
+}